@@ -1,22 +1,43 @@
+pub mod przzi_api;
+pub mod przzi_batch;
+pub mod przzi_cite;
+pub mod przzi_clipboard;
+pub mod przzi_config;
+pub mod przzi_download;
+pub mod przzi_graph;
+pub mod przzi_highlight;
+pub mod przzi_history;
+pub mod przzi_id;
+pub mod przzi_keymap;
+pub mod przzi_theme;
 pub mod przzi_tui;
+pub mod przzi_watch;
+pub mod przzi_worker;
 
-use clap::{Parser};
+use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
 use url::{Url};
-use crossterm::terminal::{enable_raw_mode, disable_raw_mode};
 use serde::{Serialize, Deserialize};
-use select::document::Document;
-use select::predicate::{Attr, Name, Predicate};
-use std::io::Write;
+use indicatif::{ProgressBar, ProgressStyle};
+use przzi_api::PRZZIClient;
+use przzi_batch::BatchConfig;
+use przzi_cite::CitationStyle;
+use przzi_graph::GraphConfig;
+use przzi_history::{HistoryConfig, HistoryEntry};
+use przzi_id::PaperId;
+use przzi_keymap::Keymap;
+use przzi_theme::Theme;
 use przzi_tui::PRZZITUI;
+use przzi_watch::{WatchCommand, WatchConfig};
 
 
 const CLI_HELP: &str = "A TUI to partially view/download research papers.
 Search Results are taken from Semantic Scholar.";
 
 #[derive(Parser)]
-#[clap(version = "0.1.1", author = "lucasace", about = CLI_HELP)]
+#[clap(version = "0.1.1", author = "lucasace", about = CLI_HELP, args_conflicts_with_subcommands = true)]
 pub struct PRZZIConfig {
-    /// Query to search for
+    /// Query to search for, after `--` when it is also a command, e.g. `paperazzi -- history`
     #[clap()]
     pub query: Option<String>,
 
@@ -24,11 +45,97 @@ pub struct PRZZIConfig {
     #[clap(short='r', long="num_results", default_value_t = 10, requires = "query")]
     pub num_results: usize,
 
-    /// download the paper via the DOI url, cannot be used when query is mentioned
+    /// download the paper by DOI (bare or doi.org url), arXiv, PubMed (pmid:) or Semantic Scholar ID,
+    /// cannot be used when query is mentioned
+    #[clap(short='d', long = "download", conflicts_with_all = &["query", "year", "venue", "fields-of-study", "min-citations"])]
+    pub download: Option<PaperId>,
 
-    #[clap(short='d', long = "download", conflicts_with = "query")]
-    pub download: Option<Url>,
-    
+    /// open a single paper by DOI, arXiv, PubMed (pmid:) or Semantic Scholar ID
+    #[clap(short='p', long = "paper", conflicts_with_all = &["query", "download", "year", "venue", "fields-of-study", "min-citations"])]
+    pub paper: Option<PaperId>,
+
+    /// find an author by name and browse their papers
+    #[clap(short='a', long = "author", conflicts_with_all = &["query", "download", "paper", "year", "venue", "fields-of-study", "min-citations"])]
+    pub author: Option<String>,
+
+    /// print the query's results or the --paper as references in this style instead of opening the TUI
+    #[clap(long = "cite", arg_enum, conflicts_with_all = &["download", "author"])]
+    pub cite: Option<CitationStyle>,
+
+    #[clap(flatten)]
+    pub filters: SearchFilters,
+
+    #[clap(subcommand)]
+    pub command: Option<PRZZICommand>,
+}
+
+#[derive(Subcommand)]
+pub enum PRZZICommand {
+    /// Download every paper listed in a file of DOIs / arXiv IDs or a BibTeX file
+    Download(BatchConfig),
+    /// Browse papers recommended from a set of seed papers
+    Recommend(RecommendConfig),
+    /// Export the citation network around seed papers as GraphML, DOT or JSON
+    Graph(GraphConfig),
+    /// List past searches or run one of them again
+    History(HistoryConfig),
+    /// Save searches and list the papers that are new since they last ran
+    Watch(WatchConfig),
+}
+
+/// Filters narrowing down a search, only taken together with a query
+#[derive(Args, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct SearchFilters {
+    /// only papers from this year or range of years, e.g. 2019, 2016-2020 or 2010-
+    #[clap(long = "year", requires = "query")]
+    pub year: Option<String>,
+
+    /// only papers from these venues, comma separated
+    #[clap(long = "venue", requires = "query")]
+    pub venue: Option<String>,
+
+    /// only papers in these fields of study, comma separated, e.g. "Computer Science,Physics"
+    #[clap(long = "fields-of-study", requires = "query")]
+    pub fields_of_study: Option<String>,
+
+    /// only papers cited at least this many times
+    #[clap(long = "min-citations", requires = "query")]
+    pub min_citations: Option<usize>,
+}
+
+impl SearchFilters {
+    /// Short summary such as `year 2019 · venue ACL`, empty when nothing is filtered
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(year) = &self.year {
+            parts.push(format!("year {}", year));
+        }
+        if let Some(venue) = &self.venue {
+            parts.push(format!("venue {}", venue));
+        }
+        if let Some(fields) = &self.fields_of_study {
+            parts.push(format!("fields {}", fields));
+        }
+        if let Some(min_citations) = self.min_citations {
+            parts.push(format!("{}+ citations", min_citations));
+        }
+        parts.join(" · ")
+    }
+}
+
+#[derive(Args)]
+pub struct RecommendConfig {
+    /// papers to find related work for (DOI, arXiv, PubMed or Semantic Scholar ID)
+    #[clap(short='s', long = "seed", required = true, multiple_occurrences = true)]
+    pub seed: Vec<PaperId>,
+
+    /// papers the recommendations should not be like
+    #[clap(short='x', long = "negative", multiple_occurrences = true)]
+    pub negative: Vec<PaperId>,
+
+    /// number of recommendations to show
+    #[clap(short='r', long="num_results", default_value_t = 10)]
+    pub num_results: usize,
 }
 
 pub struct PRZZIError {
@@ -89,140 +196,330 @@ pub struct Query {
     pub query: Option<String>,
     pub limit : usize,
     pub fields: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub venue: Option<String>,
+    #[serde(rename = "fieldsOfStudy", skip_serializing_if = "Option::is_none")]
+    pub fields_of_study: Option<String>,
+    #[serde(rename = "minCitationCount", skip_serializing_if = "Option::is_none")]
+    pub min_citations: Option<usize>,
 }
 
+impl Query {
+    pub fn new(query: &str, limit: usize, filters: &SearchFilters) -> Self {
+        Query {
+            query: Some(query.to_string()),
+            limit,
+            fields: przzi_api::PAPER_FIELDS.to_string(),
+            year: filters.year.clone(),
+            venue: filters.venue.clone(),
+            fields_of_study: filters.fields_of_study.clone(),
+            min_citations: filters.min_citations,
+        }
+    }
+}
 
-#[derive(Deserialize)]
+/// Where paperazzi keeps its search history and other state, created on first use
+pub fn data_dir() -> Result<PathBuf, PRZZIError> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| PRZZIError {
+            msg: "Could not find a data directory to save to".to_string(),
+        })?
+        .join("paperazzi");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Prints each paper as a reference, with a blank line between them
+fn print_citations(results: &[PRZZIResult], style: CitationStyle) {
+    let citations = results.iter().map(|result| przzi_cite::format(result, style)).collect::<Vec<String>>();
+    println!("{}", citations.join("\n\n"));
+}
+
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct PRZZIResult {
     pub url: Url,
     pub title: String,
     #[serde(rename = "abstract")]
     pub abs: String,
-    pub year: usize,
-    pub authors: Vec<String>
+    pub year: Option<usize>,
+    pub authors: Vec<PRZZIAuthor>,
+    /// Semantic Scholar paper ID
+    #[serde(rename = "paperId")]
+    pub paper_id: Option<String>,
+    pub doi: Option<String>,
+    pub arxiv: Option<String>,
+    pub venue: String,
+    #[serde(rename = "citationCount")]
+    pub citation_count: Option<usize>,
+    #[serde(rename = "referenceCount")]
+    pub reference_count: Option<usize>,
 }
 
 impl PRZZIResult {
     pub fn new(papers:serde_json::Value) -> Self {
-        let mut paper_url = papers["externalIds"]["DOI"].to_string().replace("\"", "");
-        if paper_url != "null" {
-            paper_url = format!("https://doi.org/{}", paper_url);
-        }
-        else{
-            paper_url = papers["externalIds"]["ArXiv"].to_string().replace("\"", "");
-            paper_url = format!("https://arxiv.org/pdf/{}.pdf", paper_url);
-        }
-        if paper_url.contains("null") {
-            paper_url = papers["url"].to_string().replace("\"", "");
-        }
+        let text = |value: &serde_json::Value| value.as_str().unwrap_or("").trim().to_string();
+        let count = |value: &serde_json::Value| value.as_u64().map(|x| x as usize);
+        let paper_id = papers["paperId"].as_str().map(|x| x.to_string());
+        let doi = papers["externalIds"]["DOI"].as_str().map(|x| x.to_string());
+        let arxiv = papers["externalIds"]["ArXiv"].as_str().map(|x| x.to_string());
+        let paper_url = if let Some(doi) = &doi {
+            format!("https://doi.org/{}", doi)
+        } else if let Some(arxiv) = &arxiv {
+            format!("https://arxiv.org/pdf/{}.pdf", arxiv)
+        } else if let Some(url) = papers["url"].as_str() {
+            url.to_string()
+        } else {
+            format!("https://www.semanticscholar.org/paper/{}", paper_id.as_deref().unwrap_or(""))
+        };
         let url = Url::parse(&paper_url).unwrap();
-        let title = papers["title"].to_string().replace("\"", "");
-        let abs = papers["abstract"].to_string().replace("\"", "");
-        let year = papers["year"].to_string().parse::<usize>().unwrap();
-        let authors : Vec<String> = papers["authors"].as_array().unwrap().iter().map(|x| x["name"].to_string().replace("\"", "")).collect();
-        let authors = authors.iter().take(4).map(|x| x.to_string()).collect();
+        let authors = papers["authors"]
+            .as_array()
+            .map(|authors| authors.iter().map(PRZZIAuthor::new).collect())
+            .unwrap_or_default();
         PRZZIResult {
             url,
-            title,
-            abs,
-            year,
+            title: text(&papers["title"]),
+            abs: text(&papers["abstract"]),
+            year: count(&papers["year"]),
             authors,
+            paper_id,
+            doi,
+            arxiv,
+            venue: text(&papers["venue"]),
+            citation_count: count(&papers["citationCount"]),
+            reference_count: count(&papers["referenceCount"]),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PRZZIAuthor {
+    pub name: String,
+    /// Semantic Scholar author ID, missing for authors S2 could not match
+    #[serde(rename = "authorId")]
+    pub author_id: Option<String>,
+}
+
+impl PRZZIAuthor {
+    pub fn new(author: &serde_json::Value) -> Self {
+        PRZZIAuthor {
+            name: author["name"].as_str().unwrap_or("").trim().to_string(),
+            author_id: author["authorId"].as_str().map(|x| x.to_string()),
+        }
+    }
+}
+
+/// Author details from the Semantic Scholar author endpoints
+#[derive(Deserialize, Clone)]
+pub struct PRZZIAuthorProfile {
+    #[serde(rename = "authorId")]
+    pub author_id: String,
+    pub name: String,
+    pub affiliations: Vec<String>,
+    #[serde(rename = "paperCount")]
+    pub paper_count: Option<usize>,
+    #[serde(rename = "citationCount")]
+    pub citation_count: Option<usize>,
+    #[serde(rename = "hIndex")]
+    pub h_index: Option<usize>,
+}
+
+impl PRZZIAuthorProfile {
+    pub fn new(author: serde_json::Value) -> Self {
+        let count = |value: &serde_json::Value| value.as_u64().map(|x| x as usize);
+        let affiliations = author["affiliations"]
+            .as_array()
+            .map(|affiliations| affiliations.iter().filter_map(|x| x.as_str()).map(|x| x.to_string()).collect())
+            .unwrap_or_default();
+        PRZZIAuthorProfile {
+            author_id: author["authorId"].as_str().unwrap_or("").to_string(),
+            name: author["name"].as_str().unwrap_or("").trim().to_string(),
+            affiliations,
+            paper_count: count(&author["paperCount"]),
+            citation_count: count(&author["citationCount"]),
+            h_index: count(&author["hIndex"]),
         }
     }
 }
 
 pub struct PRZZI {
     tui: PRZZITUI,
-    search_url: Url,
+    client: PRZZIClient,
     query: Option<String>,
     num_results: usize,
-    download: Option<Url>,
+    download: Option<PaperId>,
+    paper: Option<PaperId>,
+    author: Option<String>,
+    cite: Option<CitationStyle>,
+    filters: SearchFilters,
+    command: Option<PRZZICommand>,
 }
 
 impl PRZZI {
     pub fn new(config: PRZZIConfig) -> Result<Self, PRZZIError> {
-        if config.query.is_none() && config.download.is_none() {
+        if config.query.is_none() && config.download.is_none() && config.paper.is_none()
+            && config.author.is_none() && config.command.is_none() {
             return Err(PRZZIError {
-                msg: "Either query or download must be specified".to_string(),
+                msg: "Either query, paper, author or download must be specified".to_string(),
             });
         }
+        let user_config = przzi_config::load()?;
+        let mut tui = PRZZITUI::new();
+        tui.set_keymap(Keymap::from_config(&user_config.keys)?);
+        tui.set_theme(Theme::from_config(&user_config.theme)?);
+        tui.set_mouse(user_config.ui.mouse);
+        tui.set_citation_style(user_config.ui.citation_style);
         Ok(PRZZI {
-            tui: PRZZITUI::new(),
-            search_url: Url::parse("https://api.semanticscholar.org/graph/v1/paper/search")?,
+            tui,
+            client: PRZZIClient::new(),
             query: config.query,
             num_results: config.num_results,
             download: config.download,
+            paper: config.paper,
+            author: config.author,
+            cite: config.cite,
+            filters: config.filters,
+            command: config.command,
         })
     }
 
     pub fn run(& mut self) -> Result<(), PRZZIError> {
-        if self.query.is_some() {
-            enable_raw_mode().unwrap();
-            let results : Vec<PRZZIResult> = self.search()?;
-            self.tui.set_results(results);
+        if let Some(PRZZICommand::History(history)) = &self.command {
+            let entries = przzi_history::load()?;
+            let index = match history.run {
+                Some(index) => index,
+                None => {
+                    println!("{}", przzi_history::format_list(&entries, history.limit));
+                    return Ok(());
+                }
+            };
+            let entry = index.checked_sub(1).and_then(|i| entries.get(i)).ok_or_else(|| PRZZIError {
+                msg: format!("There is no search #{} in the history, see `paperazzi history`", index),
+            })?;
+            self.query = Some(entry.query.clone());
+            self.filters = entry.filters.clone();
+            self.num_results = entry.num_results;
+            self.command = None;
+        }
+        if let Some(PRZZICommand::Download(batch)) = &self.command {
+            let manifest = przzi_batch::run(batch)?;
+            println!("{}", przzi_batch::summary_table(&manifest));
+            if let Some(path) = &batch.manifest {
+                println!("Manifest written to {}", path.display());
+            }
+        } else if let Some(PRZZICommand::Graph(graph_config)) = &self.command {
+            let graph = przzi_graph::run(&self.client, graph_config)?;
+            eprintln!("Crawled {} papers and {} citation links", graph.nodes.len(), graph.edges.len());
+            if let Some(reason) = &graph.truncated {
+                eprintln!("Stopped early: {}", reason);
+            }
+            if let Some(output) = &graph_config.output {
+                eprintln!("Graph written to {}", output.display());
+            }
+        } else if let Some(PRZZICommand::Watch(watch)) = &self.command {
+            match &watch.command {
+                // The watches run inside the UI so there is something to look at while they load
+                WatchCommand::Run(run) if run.tui => {
+                    self.tui.set_label("Saved watches".to_string());
+                    self.tui.set_watch_run(run.name.clone());
+                    self.tui.start_ui()?;
+                },
+                _ => przzi_watch::run(&self.client, watch)?,
+            }
+        } else if let Some(PRZZICommand::Recommend(recommend)) = &self.command {
+            self.tui.set_label(format!("Recommended from {} seed(s)", recommend.seed.len()));
+            self.tui.set_recommendation_seeds(recommend.seed.clone(), recommend.negative.clone(), recommend.num_results);
+            self.tui.start_ui()?;
+        } else if let Some(query) = &self.query {
+            match self.cite {
+                Some(style) => {
+                    let results : Vec<PRZZIResult> = self.search()?;
+                    let entry = HistoryEntry::new(query.clone(), self.filters.clone(), self.num_results, results.len());
+                    if let Err(e) = przzi_history::record(&entry) {
+                        eprintln!("Could not save the search history: {}", e.msg);
+                    }
+                    if results.is_empty() {
+                        return Err(PRZZIError {
+                            msg: "No papers found for this query".to_string(),
+                        });
+                    }
+                    print_citations(&results, style);
+                },
+                None => {
+                    // The search runs inside the UI so there is something to look at while it loads
+                    self.tui.set_label(format!("Search: {}", query));
+                    self.tui.set_search(query.clone(), self.num_results, self.filters.clone());
+                    self.tui.start_ui()?;
+                },
+            }
+        } else if let Some(paper) = &self.paper {
+            if let Some(style) = self.cite {
+                let result = self.client.paper(paper)?;
+                print_citations(&[result], style);
+                return Ok(());
+            }
+            self.tui.set_label(format!("Paper: {}", paper));
+            self.tui.set_paper(paper.clone());
+            self.tui.start_ui()?;
+        } else if let Some(author) = &self.author {
+            self.tui.set_label(format!("Authors matching \"{}\"", author));
+            self.tui.set_author_search(author.clone());
             self.tui.start_ui()?;
-            disable_raw_mode().unwrap();
         } else {
-            let download_url =  Url::parse("https://sci-hub.wf/")?;
-            let doi_url = download_url.join(self.download.as_ref().unwrap().path())?;
             println!("Downloading...!");
-            if let Err(_e) = PRZZI::download_doi(doi_url) {
-                return Err(PRZZIError {
-                    msg: "Download failed! :-( \n Please Check the DOI or raise an issue on github".to_string(),
-                });
-            }
-            else {
-                println!("Download complete!!")
+            let bar = ProgressBar::new_spinner();
+            bar.set_style(
+                ProgressStyle::with_template("{spinner} [{elapsed_precise}] {bytes} {msg}")
+                    .unwrap()
+            );
+            let result = przzi_download::download_id(self.download.as_ref().unwrap(), |downloaded, total| {
+                if let Some(total) = total {
+                    if bar.length() != Some(total) {
+                        bar.set_length(total);
+                        bar.set_style(
+                            ProgressStyle::with_template("[{elapsed_precise}] {wide_bar} {bytes}/{total_bytes} ({eta})")
+                                .unwrap()
+                        );
+                    }
+                }
+                bar.set_position(downloaded);
+                true
+            });
+            bar.finish_and_clear();
+            match result {
+                Ok(path) => println!("Download complete!! Saved to {}", path.display()),
+                Err(e) => {
+                    return Err(PRZZIError {
+                        msg: format!("Download failed! :-( \n {} \n Please Check the ID or raise an issue on github", e),
+                    });
+                }
             }
         }
        Ok(())
     }
 
     pub fn search(&self) -> Result<Vec<PRZZIResult>, PRZZIError> {
-        let query = Query {
-            query: self.query.clone(),  
-            limit: self.num_results,
-            fields: "title,abstract,authors,year,url,externalIds".to_string(),
-        };
-
-        let client = reqwest::blocking::Client::new();
-        let res = client.get(self.search_url.clone())
-            .query(&query)
-            .send()?;
-        let response: serde_json::Value = serde_json::from_str(res.text()?.as_str())?;
-        let papers : Vec<serde_json::Value> = serde_json::from_value(response["data"].clone())?;
-        let results : Vec<PRZZIResult> = papers.iter().map(|x| PRZZIResult::new(x.clone())).collect();
-        Ok(results)
+        let query = Query::new(self.query.as_deref().unwrap_or(""), self.num_results, &self.filters);
+        self.client.search(&query)
     }
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    pub fn download_doi(doi_url: Url) -> Result<(), PRZZIError> {
-        let client = reqwest::blocking::Client::new();
-        let res = client.get(doi_url.clone())
-            .send()?;
-        let document = Document::from(res.text()?.as_str());
-        // Need better way to do this
-        let link = document.find(Attr("id", "buttons").descendant(Name("button")));
-        if link.count() == 0 {
-            return Err(PRZZIError {
-                msg: "Download failed! :-( \n Please Check the DOI or raise an issue on github".to_string(),
-            });
-        }
-        let link = document.find(Attr("id", "buttons").descendant(Name("button"))).next().unwrap();
-        let link = link.attr("onclick").unwrap().replace("location.href='", "").replace("'", "");
-        let down_url = doi_url.to_string();
-        let down_url = Url::parse(down_url.split("https://doi.org").next().as_ref().unwrap())?;
-        let down_url = down_url.join(&link)?;
-        let res = client.get(down_url)
-            .send()?;
-        if res.headers().get("Content-Type").unwrap() != "application/pdf" {
-            return Err(PRZZIError {
-                msg: "Download failed! :-( \n Please Check the DOI or raise an issue on github".to_string(),
-            });
-        }
-        let filename = res.url().path_segments().unwrap().last().unwrap();
-        let mut file = std::fs::File::create(filename)?;
-        file.write_all(res.bytes()?.as_ref())?;
-        Ok(())
+    #[test]
+    fn query_named_like_a_command() {
+        let config = PRZZIConfig::try_parse_from(["paperazzi", "--year", "2020", "--", "history"]).unwrap();
+        assert_eq!(config.query.as_deref(), Some("history"));
+        assert!(config.command.is_none());
+        assert_eq!(config.filters.year.as_deref(), Some("2020"));
+
+        let config = PRZZIConfig::try_parse_from(["paperazzi", "history"]).unwrap();
+        assert!(config.query.is_none());
+        assert!(matches!(config.command, Some(PRZZICommand::History(_))));
     }
 }
@@ -0,0 +1,211 @@
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use url::Url;
+
+use crate::przzi_id::PaperId;
+use crate::{PRZZIAuthorProfile, PRZZIError, PRZZIResult, Query};
+
+const GRAPH_URL: &str = "https://api.semanticscholar.org/graph/v1/";
+const RECOMMENDATIONS_URL: &str = "https://api.semanticscholar.org/recommendations/v1/";
+
+/// Name of the search provider, saved with every search in the history
+pub const PROVIDER: &str = "semanticscholar";
+
+/// Characters escaped in an ID put into a URL path. `/` is left alone, the API
+/// takes DOIs with their slashes, but `%` is escaped so a DOI is not decoded twice
+const PATH_ID: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Fields requested for every paper shown in the TUI
+pub const PAPER_FIELDS: &str =
+    "paperId,title,abstract,authors,year,url,externalIds,venue,citationCount,referenceCount";
+
+/// Fields requested for author profiles
+pub const AUTHOR_FIELDS: &str = "authorId,name,affiliations,paperCount,citationCount,hIndex";
+
+/// Blocking client for the Semantic Scholar graph and recommendations APIs
+#[derive(Clone)]
+pub struct PRZZIClient {
+    client: reqwest::blocking::Client,
+    graph_url: Url,
+    recommendations_url: Url,
+}
+
+impl Default for PRZZIClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PRZZIClient {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::blocking::Client::new(),
+            graph_url: Url::parse(GRAPH_URL).unwrap(),
+            recommendations_url: Url::parse(RECOMMENDATIONS_URL).unwrap(),
+        }
+    }
+
+    /// An ID escaped to go into a request path, e.g. `DOI:10.1002/(SICI)...%3C693::AID-ASI3%3E...`
+    fn path_id(id: &str) -> String {
+        utf8_percent_encode(id, PATH_ID).to_string()
+    }
+
+    /// GETs `path` relative to the graph API and returns the JSON body
+    fn get<T: serde::Serialize + ?Sized>(&self, path: &str, query: &T) -> Result<serde_json::Value, PRZZIError> {
+        let url = self.graph_url.join(path)?;
+        self.send(self.client.get(url).query(query))
+    }
+
+    /// Sends the request and parses the JSON body.
+    ///
+    /// Non-success responses are turned into an error carrying the API's message.
+    fn send(&self, request: reqwest::blocking::RequestBuilder) -> Result<serde_json::Value, PRZZIError> {
+        let res = request.send()?;
+        let status = res.status();
+        let text = res.text()?;
+        if !status.is_success() {
+            let response: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+            let reason = response["error"]
+                .as_str()
+                .or_else(|| response["message"].as_str())
+                .unwrap_or_else(|| status.canonical_reason().unwrap_or("request failed"));
+            return Err(PRZZIError {
+                msg: format!("Semantic Scholar responded with {}: {}", status.as_u16(), reason),
+            });
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn search(&self, query: &Query) -> Result<Vec<PRZZIResult>, PRZZIError> {
+        let response = self.get("paper/search", query)?;
+        // `data` is left out when nothing matched
+        let papers = response["data"].as_array().cloned().unwrap_or_default();
+        Ok(papers.into_iter().map(PRZZIResult::new).collect())
+    }
+
+    /// Looks up a single paper with `/paper/{id}`
+    pub fn paper(&self, id: &PaperId) -> Result<PRZZIResult, PRZZIError> {
+        let path = format!("paper/{}", Self::path_id(&id.to_s2_id()));
+        let response = self.get(&path, &[("fields", PAPER_FIELDS)])?;
+        Ok(PRZZIResult::new(response))
+    }
+
+    /// Papers cited by `paper_id`, from `/paper/{id}/references`
+    pub fn references(&self, paper_id: &str, limit: usize) -> Result<Vec<PRZZIResult>, PRZZIError> {
+        self.linked_papers(paper_id, "references", "citedPaper", limit)
+    }
+
+    /// Papers citing `paper_id`, from `/paper/{id}/citations`
+    pub fn citations(&self, paper_id: &str, limit: usize) -> Result<Vec<PRZZIResult>, PRZZIError> {
+        self.linked_papers(paper_id, "citations", "citingPaper", limit)
+    }
+
+    fn linked_papers(&self, paper_id: &str, endpoint: &str, key: &str, limit: usize) -> Result<Vec<PRZZIResult>, PRZZIError> {
+        let path = format!("paper/{}/{}", Self::path_id(paper_id), endpoint);
+        let limit = limit.to_string();
+        let response = self.get(&path, &[("fields", PAPER_FIELDS), ("limit", limit.as_str())])?;
+        let links = response["data"].as_array().cloned().unwrap_or_default();
+        Ok(links
+            .into_iter()
+            .map(|link| link[key].clone())
+            // References to papers outside the corpus come back without a title
+            .filter(|paper| paper["title"].is_string())
+            .map(PRZZIResult::new)
+            .collect())
+    }
+
+    /// Author profile from `/author/{id}`
+    pub fn author(&self, author_id: &str) -> Result<PRZZIAuthorProfile, PRZZIError> {
+        let path = format!("author/{}", Self::path_id(author_id));
+        let response = self.get(&path, &[("fields", AUTHOR_FIELDS)])?;
+        Ok(PRZZIAuthorProfile::new(response))
+    }
+
+    /// Papers written by the author, from `/author/{id}/papers`
+    pub fn author_papers(&self, author_id: &str, limit: usize) -> Result<Vec<PRZZIResult>, PRZZIError> {
+        let path = format!("author/{}/papers", Self::path_id(author_id));
+        let limit = limit.to_string();
+        let response = self.get(&path, &[("fields", PAPER_FIELDS), ("limit", limit.as_str())])?;
+        let papers = response["data"].as_array().cloned().unwrap_or_default();
+        Ok(papers.into_iter().map(PRZZIResult::new).collect())
+    }
+
+    /// Authors matching a name, from `/author/search`
+    pub fn search_authors(&self, name: &str, limit: usize) -> Result<Vec<PRZZIAuthorProfile>, PRZZIError> {
+        let limit = limit.to_string();
+        let response = self.get(
+            "author/search",
+            &[("query", name), ("fields", AUTHOR_FIELDS), ("limit", limit.as_str())],
+        )?;
+        let authors = response["data"].as_array().cloned().unwrap_or_default();
+        Ok(authors.into_iter().map(PRZZIAuthorProfile::new).collect())
+    }
+
+    /// Papers similar to the `positive` seeds and unlike the `negative` ones, from the recommendations API
+    pub fn recommendations(&self, positive: &[String], negative: &[String], limit: usize) -> Result<Vec<PRZZIResult>, PRZZIError> {
+        let url = self.recommendations_url.join("papers/")?;
+        let body = serde_json::json!({
+            "positivePaperIds": positive,
+            "negativePaperIds": negative,
+        });
+        let limit = limit.to_string();
+        let request = self
+            .client
+            .post(url)
+            .query(&[("fields", PAPER_FIELDS), ("limit", limit.as_str())])
+            .json(&body);
+        let response = self.send(request)?;
+        let papers = response["recommendedPapers"].as_array().cloned().unwrap_or_default();
+        Ok(papers.into_iter().map(PRZZIResult::new).collect())
+    }
+
+    /// The Semantic Scholar paper ID behind any identifier, looking it up when needed
+    pub fn resolve_paper_id(&self, id: &PaperId) -> Result<String, PRZZIError> {
+        if let PaperId::SemanticScholar(sha) = id {
+            return Ok(sha.clone());
+        }
+        self.paper(id)?.paper_id.ok_or_else(|| PRZZIError {
+            msg: format!("{} is not on Semantic Scholar", id),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paper_paths() {
+        let client = PRZZIClient::new();
+        let cases = [
+            ("10.1109/ICCV.2017.405", "/graph/v1/paper/DOI:10.1109/iccv.2017.405"),
+            (
+                "doi:10.1002/(SICI)1097-4571(199806)49:8<693::AID-ASI3>3.0.CO;2-0",
+                "/graph/v1/paper/DOI:10.1002/(sici)1097-4571(199806)49:8%3C693::aid-asi3%3E3.0.co;2-0",
+            ),
+            ("doi:10.1000/a#b?c%d", "/graph/v1/paper/DOI:10.1000/a%23b%3Fc%25d"),
+        ];
+        for (id, expected) in cases {
+            let id: PaperId = id.parse().unwrap();
+            let path = format!("paper/{}", PRZZIClient::path_id(&id.to_s2_id()));
+            let url = client.graph_url.join(&path).unwrap();
+            assert_eq!(url.path(), expected, "{:?}", id);
+            assert_eq!(url.query(), None);
+            assert_eq!(url.fragment(), None);
+        }
+    }
+}
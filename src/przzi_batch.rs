@@ -0,0 +1,413 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use clap::Args;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Serialize;
+
+use crate::przzi_download;
+use crate::przzi_id::PaperId;
+use crate::PRZZIError;
+
+/// Download every paper listed in a file
+#[derive(Args)]
+pub struct BatchConfig {
+    /// file with one DOI, arXiv, PubMed or Semantic Scholar ID per line, or a BibTeX file
+    #[clap(long = "from")]
+    pub from: PathBuf,
+
+    /// number of papers to download at the same time
+    #[clap(short = 'j', long = "jobs", default_value_t = 4)]
+    pub jobs: usize,
+
+    /// write a JSON manifest of the results to this file
+    #[clap(short = 'm', long = "manifest")]
+    pub manifest: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchStatus {
+    Downloaded,
+    Failed,
+}
+
+#[derive(Serialize)]
+pub struct BatchEntry {
+    pub id: String,
+    pub status: BatchStatus,
+    pub file: Option<PathBuf>,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BatchManifest {
+    pub source: PathBuf,
+    pub downloaded: usize,
+    pub failed: usize,
+    pub entries: Vec<BatchEntry>,
+}
+
+/// Reads the identifiers out of a plain list or a BibTeX file, each paper only once
+pub fn read_identifiers(path: &Path) -> Result<Vec<String>, PRZZIError> {
+    let contents = fs::read_to_string(path)?;
+    let is_bibtex = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bib"))
+        || contents.trim_start().starts_with('@');
+    let ids = if is_bibtex {
+        bibtex_identifiers(&contents)
+    } else {
+        contents
+            .lines()
+            .map(strip_comment)
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_string())
+            .collect()
+    };
+    Ok(dedupe(ids))
+}
+
+/// The line without a `#` comment, which starts the line or follows whitespace.
+/// A `#` inside an identifier, like a URL fragment, is kept
+fn strip_comment(line: &str) -> &str {
+    let line = line.trim();
+    let comment = line
+        .char_indices()
+        .find(|&(at, c)| c == '#' && (at == 0 || line[..at].ends_with(char::is_whitespace)))
+        .map_or(line.len(), |(at, _)| at);
+    line[..comment].trim_end()
+}
+
+/// Drops repeated identifiers, keeping the first. Two spellings of the same ID,
+/// e.g. a DOI and its doi.org link, count as the same paper
+fn dedupe(ids: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    ids.into_iter()
+        .filter(|id| {
+            let key = id
+                .parse::<PaperId>()
+                .map_or_else(|_| id.clone(), |id| id.to_string());
+            seen.insert(key)
+        })
+        .collect()
+}
+
+fn bibtex_identifiers(contents: &str) -> Vec<String> {
+    bibtex_entries(contents)
+        .into_iter()
+        .filter_map(|entry| {
+            bibtex_field(entry, "doi")
+                .or_else(|| bibtex_field(entry, "eprint"))
+                .or_else(|| bibtex_field(entry, "url"))
+        })
+        .collect()
+}
+
+/// The entries of a BibTeX file without their closing delimiter, `article{key, title = {...}`.
+///
+/// Only an `@` between entries starts one, so an `@` in a field value (an email, a URL) stays
+/// part of its entry. `@comment`, `@string` and `@preamble` blocks are skipped
+fn bibtex_entries(contents: &str) -> Vec<&str> {
+    let mut entries = Vec::new();
+    let mut rest = contents;
+    while let Some(at) = rest.find('@') {
+        rest = &rest[at + 1..];
+        let Some(open) = rest.find(['{', '(']) else { break };
+        let kind = rest[..open].trim();
+        if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric()) {
+            // Not an entry, e.g. an email address in the text between entries
+            continue;
+        }
+        let end = open + entry_end(&rest[open..]).unwrap_or(rest.len() - open);
+        if !["comment", "string", "preamble"].iter().any(|skip| kind.eq_ignore_ascii_case(skip)) {
+            entries.push(&rest[..end]);
+        }
+        rest = &rest[end..];
+    }
+    entries
+}
+
+/// Byte offset of the `}` or `)` closing the entry body that `body` starts with
+fn entry_end(body: &str) -> Option<usize> {
+    if body.starts_with('{') {
+        return closing_brace(body);
+    }
+    let mut depth = 0;
+    let mut quoted = false;
+    for (at, c) in body.char_indices().skip(1) {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '"' if depth == 0 => quoted = !quoted,
+            ')' if depth == 0 && !quoted => return Some(at),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The value of `name = {value}`, `name = "value"` or `name = value` in a BibTeX entry.
+///
+/// Fields are read one after the other, so `name = ...` inside another field's value is skipped
+fn bibtex_field(entry: &str, name: &str) -> Option<String> {
+    // Skip the entry type and key, `article{key,`
+    let mut rest = &entry[entry.find(',')? + 1..];
+    loop {
+        let (field, value) = rest.split_once('=')?;
+        let value = value.trim_start();
+        let (value, after) = match value.chars().next()? {
+            '{' => {
+                let end = closing_brace(value)?;
+                (&value[1..end], &value[end + 1..])
+            }
+            '"' => {
+                let end = value[1..].find('"')? + 1;
+                (&value[1..end], &value[end + 1..])
+            }
+            _ => {
+                let end = value.find([',', '\n', '}']).unwrap_or(value.len());
+                (&value[..end], &value[end..])
+            }
+        };
+        if field.trim().eq_ignore_ascii_case(name) {
+            return Some(value.trim().to_string());
+        }
+        rest = after.trim_start().strip_prefix(',')?;
+    }
+}
+
+/// Byte offset of the `}` closing the `{` that `value` starts with
+fn closing_brace(value: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (at, c) in value.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(at);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn download_one<F>(id: &str, on_progress: F) -> Result<PathBuf, String>
+where
+    F: FnMut(u64, Option<u64>) -> bool,
+{
+    let id: PaperId = id.parse()?;
+    przzi_download::download_id(&id, on_progress).map_err(|e| e.to_string())
+}
+
+/// Downloads every identifier in `config.from` with at most `config.jobs` downloads at once
+pub fn run(config: &BatchConfig) -> Result<BatchManifest, PRZZIError> {
+    let ids = read_identifiers(&config.from)?;
+    if ids.is_empty() {
+        return Err(PRZZIError {
+            msg: format!("No identifiers found in {}", config.from.display()),
+        });
+    }
+    let jobs = config.jobs.clamp(1, ids.len());
+    let queue: Arc<Mutex<VecDeque<(usize, String)>>> =
+        Arc::new(Mutex::new(ids.iter().cloned().enumerate().collect()));
+    let (tx, rx) = mpsc::channel();
+
+    let bars = MultiProgress::new();
+    let overall = bars.add(ProgressBar::new(ids.len() as u64));
+    overall.set_style(ProgressStyle::with_template("[{elapsed_precise}] {wide_bar} {pos}/{len} papers").unwrap());
+    let item_style = ProgressStyle::with_template("  {spinner} {msg} {bytes}").unwrap();
+
+    let workers: Vec<_> = (0..jobs)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let tx = tx.clone();
+            let bar = bars.add(ProgressBar::new_spinner());
+            bar.set_style(item_style.clone());
+            thread::spawn(move || {
+                loop {
+                    let next = queue.lock().unwrap().pop_front();
+                    let Some((index, id)) = next else { break };
+                    bar.set_message(id.clone());
+                    bar.set_position(0);
+                    let result = download_one(&id, |downloaded, _total| {
+                        bar.set_position(downloaded);
+                        true
+                    });
+                    if tx.send((index, id, result)).is_err() {
+                        break;
+                    }
+                }
+                bar.finish_and_clear();
+            })
+        })
+        .collect();
+    drop(tx);
+
+    let mut entries: Vec<Option<BatchEntry>> = (0..ids.len()).map(|_| None).collect();
+    for (index, id, result) in rx {
+        overall.inc(1);
+        entries[index] = Some(match result {
+            Ok(file) => BatchEntry {
+                id,
+                status: BatchStatus::Downloaded,
+                file: Some(file),
+                reason: None,
+            },
+            Err(reason) => BatchEntry {
+                id,
+                status: BatchStatus::Failed,
+                file: None,
+                reason: Some(reason),
+            },
+        });
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+    overall.finish_and_clear();
+
+    let entries: Vec<BatchEntry> = entries.into_iter().flatten().collect();
+    let downloaded = entries
+        .iter()
+        .filter(|entry| matches!(entry.status, BatchStatus::Downloaded))
+        .count();
+    let manifest = BatchManifest {
+        source: config.from.clone(),
+        downloaded,
+        failed: entries.len() - downloaded,
+        entries,
+    };
+    if let Some(path) = &config.manifest {
+        fs::write(path, serde_json::to_string_pretty(&manifest)?)?;
+    }
+    Ok(manifest)
+}
+
+/// Summary table printed once the batch is done
+pub fn summary_table(manifest: &BatchManifest) -> String {
+    let rows: Vec<[String; 4]> = manifest
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let (status, detail) = match entry.status {
+                BatchStatus::Downloaded => (
+                    "ok",
+                    entry.file.as_ref().map(|f| f.display().to_string()).unwrap_or_default(),
+                ),
+                BatchStatus::Failed => ("failed", entry.reason.clone().unwrap_or_default()),
+            };
+            [(index + 1).to_string(), entry.id.clone(), status.to_string(), detail]
+        })
+        .collect();
+    let header = ["#", "Identifier", "Status", "File / Reason"].map(|h| h.to_string());
+    let mut widths = header.clone().map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let format_row = |row: &[String; 4]| {
+        row.iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut table = vec![format_row(&header)];
+    table.push(widths.map(|w| "-".repeat(w)).join("  "));
+    table.extend(rows.iter().map(format_row));
+    table.push(format!(
+        "\n{} downloaded, {} failed",
+        manifest.downloaded, manifest.failed
+    ));
+    table.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments() {
+        let cases = [
+            ("10.1109/ICCV.2017.405", "10.1109/ICCV.2017.405"),
+            ("  # a comment", ""),
+            ("#10.1109/ICCV.2017.405", ""),
+            ("1706.03762 # attention", "1706.03762"),
+            ("1706.03762\t#attention", "1706.03762"),
+            ("https://example.org/paper.pdf#page=2", "https://example.org/paper.pdf#page=2"),
+            ("10.1000/a#b # note", "10.1000/a#b"),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(strip_comment(line), expected, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn bibtex_fields() {
+        let entry = "article{vaswani2017,\n  title = {{Attention} Is All You Need},\n  DOI = \"10.5555/3295222.3295349\",\n  \
+            eprint={1706.03762},\n  year = 2017,\n  urldate = {2024-01-01},\n  url = {https://arxiv.org/abs/1706.03762}\n}";
+        let cases = [
+            ("doi", Some("10.5555/3295222.3295349")),
+            ("eprint", Some("1706.03762")),
+            ("year", Some("2017")),
+            ("url", Some("https://arxiv.org/abs/1706.03762")),
+            ("title", Some("{Attention} Is All You Need")),
+            ("note", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(bibtex_field(entry, name).as_deref(), expected, "{}", name);
+        }
+        // `doi` inside another field's name or value is not the field
+        assert_eq!(bibtex_field("misc{key, mydoi = {1}, note = {doi = 2}}", "doi"), None);
+        assert_eq!(bibtex_field("misc{key, doi = {10.1/x", "doi"), None);
+    }
+
+    #[test]
+    fn bibtex_files() {
+        let contents = "% exported\n@article{a, doi = {10.1000/182}}\n@misc{b, eprint = {1706.03762}}\n\
+            @misc{c, title = {No ID}}\n@misc{d, url = {https://doi.org/10.1000/182}}";
+        assert_eq!(bibtex_identifiers(contents), ["10.1000/182", "1706.03762", "https://doi.org/10.1000/182"]);
+    }
+
+    #[test]
+    fn bibtex_entry_boundaries() {
+        let contents = "@string{acm = \"ACM\"}\n@comment{@misc{skipped, doi = {10.1/skipped}}}\n\
+            @article{a, note = {Ask me@example.org}, doi = {10.1000/182}}\n\
+            Mail jane@example.org for the PDFs\n\
+            @misc(b, howpublished = \"https://x.org/@lab\", eprint = 1706.03762)\n\
+            @preamble{\"@misc{no, doi = {10.1/no}}\"}\n\
+            @misc{c, url = {https://example.org/@me/paper.pdf}}";
+        assert_eq!(
+            bibtex_identifiers(contents),
+            ["10.1000/182", "1706.03762", "https://example.org/@me/paper.pdf"]
+        );
+    }
+
+    #[test]
+    fn dedupes_in_order() {
+        let ids = [
+            "1706.03762",
+            "10.1109/ICCV.2017.405",
+            "arXiv:1706.03762",
+            "https://doi.org/10.1109/iccv.2017.405",
+            "not an id",
+            "not an id",
+            "pmid:123",
+        ]
+        .map(String::from);
+        assert_eq!(
+            dedupe(ids.to_vec()),
+            ["1706.03762", "10.1109/ICCV.2017.405", "not an id", "pmid:123"]
+        );
+    }
+}
@@ -0,0 +1,412 @@
+use clap::ArgEnum;
+use serde::Deserialize;
+
+use crate::{PRZZIAuthor, PRZZIResult};
+
+/// Reference styles papers can be cited in
+#[derive(ArgEnum, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CitationStyle {
+    #[default]
+    Apa,
+    Mla,
+    Chicago,
+    Ieee,
+    Bibtex,
+}
+
+impl CitationStyle {
+    pub fn name(self) -> &'static str {
+        match self {
+            CitationStyle::Apa => "APA",
+            CitationStyle::Mla => "MLA",
+            CitationStyle::Chicago => "Chicago",
+            CitationStyle::Ieee => "IEEE",
+            CitationStyle::Bibtex => "BibTeX",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            CitationStyle::Apa => CitationStyle::Mla,
+            CitationStyle::Mla => CitationStyle::Chicago,
+            CitationStyle::Chicago => CitationStyle::Ieee,
+            CitationStyle::Ieee => CitationStyle::Bibtex,
+            CitationStyle::Bibtex => CitationStyle::Apa,
+        }
+    }
+}
+
+/// Words skipped when picking the title word of a citation key
+const KEY_STOP_WORDS: [&str; 8] = ["a", "an", "the", "on", "of", "for", "in", "and"];
+
+/// Last word of a name, lowercased and without accents or punctuation, e.g. `vaswani`
+fn last_name(name: &str) -> String {
+    name.split_whitespace()
+        .last()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// A key like `vaswani2017attention`: first author, year and first meaningful word of the title
+pub fn citation_key(result: &PRZZIResult) -> String {
+    let author = result.authors.first().map(|author| last_name(&author.name)).unwrap_or_default();
+    let year = result.year.map(|year| year.to_string()).unwrap_or_default();
+    let word = result
+        .title
+        .split_whitespace()
+        .map(|word| word.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase())
+        .find(|word| !word.is_empty() && !KEY_STOP_WORDS.contains(&word.as_str()))
+        .unwrap_or_default();
+    let key = format!("{}{}{}", author, year, word);
+    if key.is_empty() {
+        result.paper_id.clone().unwrap_or_else(|| "paper".to_string())
+    } else {
+        key
+    }
+}
+
+/// The paper as a BibTeX entry
+pub fn bibtex(result: &PRZZIResult) -> String {
+    let mut fields: Vec<(&str, String)> = vec![("title", format!("{{{}}}", result.title))];
+    if !result.authors.is_empty() {
+        let authors = result.authors.iter().map(|author| author.name.as_str()).collect::<Vec<&str>>();
+        fields.push(("author", authors.join(" and ")));
+    }
+    if let Some(year) = result.year {
+        fields.push(("year", year.to_string()));
+    }
+    if !result.venue.is_empty() {
+        fields.push(("journal", result.venue.clone()));
+    }
+    if let Some(doi) = &result.doi {
+        fields.push(("doi", doi.clone()));
+    }
+    if let Some(arxiv) = &result.arxiv {
+        fields.push(("eprint", arxiv.clone()));
+        fields.push(("archivePrefix", "arXiv".to_string()));
+    }
+    fields.push(("url", result.url.to_string()));
+    let kind = if result.venue.is_empty() { "misc" } else { "article" };
+    let body = fields
+        .iter()
+        .map(|(name, value)| format!("  {} = {{{}}},", name, value))
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!("@{}{{{},\n{}\n}}", kind, citation_key(result), body)
+}
+
+/// An author's name split into given names and family name, taking the last word as the family name
+fn split_name(author: &PRZZIAuthor) -> (Vec<&str>, &str) {
+    let mut words: Vec<&str> = author.name.split_whitespace().collect();
+    let family = words.pop().unwrap_or("");
+    (words, family)
+}
+
+/// `A. N.` for `Aidan Nicholas`
+fn initials(given: &[&str]) -> String {
+    given
+        .iter()
+        .filter_map(|name| name.chars().next())
+        .map(|initial| format!("{}.", initial))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// `Gomez, A. N.`
+fn family_initials(author: &PRZZIAuthor) -> String {
+    let (given, family) = split_name(author);
+    if given.is_empty() {
+        family.to_string()
+    } else {
+        format!("{}, {}", family, initials(&given))
+    }
+}
+
+/// `A. N. Gomez`
+fn initials_family(author: &PRZZIAuthor) -> String {
+    let (given, family) = split_name(author);
+    if given.is_empty() {
+        family.to_string()
+    } else {
+        format!("{} {}", initials(&given), family)
+    }
+}
+
+/// `Gomez, Aidan Nicholas`
+fn family_given(author: &PRZZIAuthor) -> String {
+    let (given, family) = split_name(author);
+    if given.is_empty() {
+        family.to_string()
+    } else {
+        format!("{}, {}", family, given.join(" "))
+    }
+}
+
+/// Names joined as `A, B, and C`, or `A and B` for two
+fn join_names(names: &[String], and: &str) -> String {
+    match names {
+        [] => String::new(),
+        [only] => only.clone(),
+        [first, second] => format!("{} {} {}", first, and, second),
+        [rest @ .., last] => format!("{}, {} {}", rest.join(", "), and, last),
+    }
+}
+
+/// Ends a piece of the reference with `mark` unless it already ends with punctuation
+fn end_with(text: &str, mark: char) -> String {
+    if text.ends_with(['.', '?', '!']) {
+        text.to_string()
+    } else {
+        format!("{}{}", text, mark)
+    }
+}
+
+/// The DOI as a link, or else the paper's URL
+fn link(result: &PRZZIResult) -> String {
+    match &result.doi {
+        Some(doi) => format!("https://doi.org/{}", doi),
+        None => result.url.to_string(),
+    }
+}
+
+/// Vaswani, A., Shazeer, N., & Parmar, N. (2017). Attention is all you need. Venue. https://doi.org/...
+fn apa(result: &PRZZIResult) -> String {
+    let names = result.authors.iter().map(family_initials).collect::<Vec<String>>();
+    // APA lists up to 20 authors, after that the first 19, an ellipsis and the last
+    let authors = match names.as_slice() {
+        [] => String::new(),
+        [only] => only.clone(),
+        [first @ .., last] if names.len() <= 20 => format!("{}, & {}", first.join(", "), last),
+        [first @ .., last] => format!("{}, . . . {}", first[..19].join(", "), last),
+    };
+    let year = result.year.map(|year| year.to_string()).unwrap_or_else(|| "n.d.".to_string());
+    let mut parts = Vec::new();
+    if authors.is_empty() {
+        parts.push(end_with(&result.title, '.'));
+        parts.push(format!("({}).", year));
+    } else {
+        parts.push(end_with(&authors, '.'));
+        parts.push(format!("({}).", year));
+        parts.push(end_with(&result.title, '.'));
+    }
+    if !result.venue.is_empty() {
+        parts.push(end_with(&result.venue, '.'));
+    }
+    parts.push(link(result));
+    parts.join(" ")
+}
+
+/// Vaswani, Ashish, et al. "Attention Is All You Need." Venue, 2017, https://doi.org/...
+fn mla(result: &PRZZIResult) -> String {
+    let authors = match result.authors.as_slice() {
+        [] => String::new(),
+        [only] => family_given(only),
+        [first, second] => format!("{}, and {}", family_given(first), second.name),
+        [first, ..] => format!("{}, et al", family_given(first)),
+    };
+    let mut parts = Vec::new();
+    if !authors.is_empty() {
+        parts.push(end_with(&authors, '.'));
+    }
+    parts.push(format!("\"{}\"", end_with(&result.title, '.')));
+    let mut container = Vec::new();
+    if !result.venue.is_empty() {
+        container.push(result.venue.clone());
+    }
+    if let Some(year) = result.year {
+        container.push(year.to_string());
+    }
+    container.push(link(result));
+    parts.push(format!("{}.", container.join(", ")));
+    parts.join(" ")
+}
+
+/// Vaswani, Ashish, Noam Shazeer, and Niki Parmar. "Attention Is All You Need." Venue, 2017. https://doi.org/...
+fn chicago(result: &PRZZIResult) -> String {
+    let mut names = result
+        .authors
+        .iter()
+        .enumerate()
+        .map(|(index, author)| if index == 0 { family_given(author) } else { author.name.clone() })
+        .collect::<Vec<String>>();
+    // More than ten authors are cut down to the first seven
+    let authors = match names.len() {
+        11.. => {
+            names.truncate(7);
+            format!("{}, et al", names.join(", "))
+        },
+        // The first name is inverted, so even two names take a comma
+        2 => format!("{}, and {}", names[0], names[1]),
+        _ => join_names(&names, "and"),
+    };
+    let mut parts = Vec::new();
+    if !authors.is_empty() {
+        parts.push(end_with(&authors, '.'));
+    }
+    parts.push(format!("\"{}\"", end_with(&result.title, '.')));
+    let published = [Some(result.venue.clone()).filter(|venue| !venue.is_empty()), result.year.map(|year| year.to_string())]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<String>>();
+    if !published.is_empty() {
+        parts.push(format!("{}.", published.join(", ")));
+    }
+    parts.push(format!("{}.", link(result)));
+    parts.join(" ")
+}
+
+/// A. Vaswani, N. Shazeer, and N. Parmar, "Attention is all you need," in Venue, 2017, doi: 10....
+fn ieee(result: &PRZZIResult) -> String {
+    let names = result.authors.iter().map(initials_family).collect::<Vec<String>>();
+    // IEEE lists up to six authors, more are cut down to the first one
+    let authors = if names.len() > 6 {
+        format!("{} et al.", names[0])
+    } else {
+        join_names(&names, "and")
+    };
+    let mut parts = Vec::new();
+    if !authors.is_empty() {
+        parts.push(format!("{},", authors));
+    }
+    let title = if result.title.ends_with(['?', '!']) {
+        format!("\"{}\"", result.title)
+    } else {
+        format!("\"{},\"", result.title)
+    };
+    parts.push(title);
+    let mut published = Vec::new();
+    if !result.venue.is_empty() {
+        published.push(format!("in {}", result.venue));
+    }
+    if let Some(year) = result.year {
+        published.push(year.to_string());
+    }
+    match &result.doi {
+        Some(doi) => published.push(format!("doi: {}", doi)),
+        None => published.push(format!("[Online]. Available: {}", result.url)),
+    }
+    parts.push(end_with(&published.join(", "), '.'));
+    parts.join(" ")
+}
+
+/// The paper as a reference in the given style
+pub fn format(result: &PRZZIResult, style: CitationStyle) -> String {
+    match style {
+        CitationStyle::Apa => apa(result),
+        CitationStyle::Mla => mla(result),
+        CitationStyle::Chicago => chicago(result),
+        CitationStyle::Ieee => ieee(result),
+        CitationStyle::Bibtex => bibtex(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper(authors: &[&str], venue: &str, doi: Option<&str>) -> PRZZIResult {
+        PRZZIResult::new(serde_json::json!({
+            "paperId": "204e3073870fae3d05bcbc2f6a8e263d9b72e776",
+            "title": "Attention Is All You Need",
+            "year": 2017,
+            "venue": venue,
+            "url": "https://www.semanticscholar.org/paper/204e3073870fae3d05bcbc2f6a8e263d9b72e776",
+            "externalIds": { "DOI": doi, "ArXiv": "1706.03762" },
+            "authors": authors.iter().map(|name| serde_json::json!({ "name": name })).collect::<Vec<_>>(),
+        }))
+    }
+
+    fn three() -> PRZZIResult {
+        paper(&["Ashish Vaswani", "Noam Shazeer", "Niki Parmar"], "NeurIPS", Some("10.5555/3295222.3295349"))
+    }
+
+    #[test]
+    fn styles() {
+        let cases = [
+            (
+                CitationStyle::Apa,
+                "Vaswani, A., Shazeer, N., & Parmar, N. (2017). Attention Is All You Need. NeurIPS. https://doi.org/10.5555/3295222.3295349",
+            ),
+            (
+                CitationStyle::Mla,
+                "Vaswani, Ashish, et al. \"Attention Is All You Need.\" NeurIPS, 2017, https://doi.org/10.5555/3295222.3295349.",
+            ),
+            (
+                CitationStyle::Chicago,
+                "Vaswani, Ashish, Noam Shazeer, and Niki Parmar. \"Attention Is All You Need.\" NeurIPS, 2017. https://doi.org/10.5555/3295222.3295349.",
+            ),
+            (
+                CitationStyle::Ieee,
+                "A. Vaswani, N. Shazeer, and N. Parmar, \"Attention Is All You Need,\" in NeurIPS, 2017, doi: 10.5555/3295222.3295349.",
+            ),
+        ];
+        for (style, expected) in cases {
+            assert_eq!(format(&three(), style), expected, "{}", style.name());
+        }
+    }
+
+    #[test]
+    fn few_authors() {
+        let two = paper(&["Ashish Vaswani", "Noam Shazeer"], "", None);
+        let cases = [
+            (CitationStyle::Apa, "Vaswani, A., & Shazeer, N. (2017). Attention Is All You Need. https://arxiv.org/pdf/1706.03762.pdf"),
+            (CitationStyle::Mla, "Vaswani, Ashish, and Noam Shazeer. \"Attention Is All You Need.\" 2017, https://arxiv.org/pdf/1706.03762.pdf."),
+            (CitationStyle::Chicago, "Vaswani, Ashish, and Noam Shazeer. \"Attention Is All You Need.\" 2017. https://arxiv.org/pdf/1706.03762.pdf."),
+            (CitationStyle::Ieee, "A. Vaswani and N. Shazeer, \"Attention Is All You Need,\" 2017, [Online]. Available: https://arxiv.org/pdf/1706.03762.pdf."),
+        ];
+        for (style, expected) in cases {
+            assert_eq!(format(&two, style), expected, "{}", style.name());
+        }
+
+        let anonymous = paper(&[], "NeurIPS", None);
+        assert_eq!(
+            format(&anonymous, CitationStyle::Apa),
+            "Attention Is All You Need. (2017). NeurIPS. https://arxiv.org/pdf/1706.03762.pdf"
+        );
+        assert_eq!(
+            format(&anonymous, CitationStyle::Ieee),
+            "\"Attention Is All You Need,\" in NeurIPS, 2017, [Online]. Available: https://arxiv.org/pdf/1706.03762.pdf."
+        );
+    }
+
+    #[test]
+    fn many_authors() {
+        let names: Vec<String> = (1..=22).map(|n| format!("Author Number{}", n)).collect();
+        let many = paper(&names.iter().map(String::as_str).collect::<Vec<&str>>(), "NeurIPS", Some("10.1/x"));
+        let apa = format(&many, CitationStyle::Apa);
+        assert!(apa.starts_with("Number1, A., Number2, A.,"), "{}", apa);
+        assert!(apa.contains("Number19, A., . . . Number22, A. (2017)."), "{}", apa);
+        assert!(!apa.contains("Number20"), "{}", apa);
+        assert!(format(&many, CitationStyle::Chicago)
+            .starts_with("Number1, Author, Author Number2, Author Number3, Author Number4, Author Number5, Author Number6, Author Number7, et al. \""));
+        assert!(format(&many, CitationStyle::Ieee).starts_with("A. Number1 et al., \""));
+    }
+
+    #[test]
+    fn bibtex_entries() {
+        assert_eq!(
+            format(&three(), CitationStyle::Bibtex),
+            "@article{vaswani2017attention,\n  title = {{Attention Is All You Need}},\n  author = {Ashish Vaswani and Noam Shazeer and Niki Parmar},\n  year = {2017},\n  journal = {NeurIPS},\n  doi = {10.5555/3295222.3295349},\n  eprint = {1706.03762},\n  archivePrefix = {arXiv},\n  url = {https://doi.org/10.5555/3295222.3295349},\n}"
+        );
+        let misc = format(&paper(&[], "", None), CitationStyle::Bibtex);
+        assert!(misc.starts_with("@misc{2017attention,\n"), "{}", misc);
+        assert!(!misc.contains("author ="), "{}", misc);
+    }
+
+    #[test]
+    fn keys() {
+        let mut result = three();
+        assert_eq!(citation_key(&result), "vaswani2017attention");
+        result.title = "On the \"Origin\" of Species".to_string();
+        result.authors[0].name = "Sean O'Neil".to_string();
+        assert_eq!(citation_key(&result), "oneil2017origin");
+        result.title = String::new();
+        result.authors.clear();
+        result.year = None;
+        assert_eq!(citation_key(&result), "204e3073870fae3d05bcbc2f6a8e263d9b72e776");
+    }
+}
@@ -0,0 +1,114 @@
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crate::PRZZIError;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Programs that set the clipboard of a local desktop, with their arguments
+/// and the variable that tells the session has one
+const CLIPBOARD_TOOLS: [(&str, &[&str], &str); 2] = [
+    ("wl-copy", &[], "WAYLAND_DISPLAY"),
+    ("xclip", &["-selection", "clipboard"], "DISPLAY"),
+];
+
+fn base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Asks the terminal to set the clipboard with an OSC 52 sequence. This works over SSH too,
+/// but there is no telling whether the terminal supports it
+fn osc52(text: &str) -> io::Result<()> {
+    let mut sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    // tmux only hands sequences on to the terminal when they are wrapped
+    if env::var_os("TMUX").is_some() {
+        sequence = format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+    }
+    let mut stdout = io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()
+}
+
+fn over_ssh() -> bool {
+    env::var_os("SSH_TTY").is_some() || env::var_os("SSH_CONNECTION").is_some()
+}
+
+/// Pipes the text into a clipboard program, failing when it is not installed
+fn pipe(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{} exited with {}", program, status)))
+    }
+}
+
+/// How text was put on the clipboard
+pub enum Copied {
+    /// A clipboard program took it, e.g. `wl-copy`
+    Tool(&'static str),
+    /// It was only sent to the terminal with OSC 52, which may have ignored it
+    Terminal,
+}
+
+/// Puts text on the clipboard with `wl-copy` / `xclip` when one is installed and this is not
+/// an SSH session (they would set the remote machine's clipboard), or else with OSC 52.
+///
+/// Only a clipboard program confirms the copy, an OSC 52 sequence is written out blindly
+pub fn copy(text: &str) -> Result<Copied, PRZZIError> {
+    if !over_ssh() {
+        for (program, args, session) in CLIPBOARD_TOOLS {
+            if env::var_os(session).is_some() && pipe(program, args, text).is_ok() {
+                return Ok(Copied::Tool(program));
+            }
+        }
+    }
+    match osc52(text) {
+        Ok(()) => Ok(Copied::Terminal),
+        Err(e) => Err(PRZZIError {
+            msg: format!("Neither wl-copy / xclip nor the terminal could be reached: {}", e),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_base64() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("10.1109/ICCV.2017.405", "MTAuMTEwOS9JQ0NWLjIwMTcuNDA1"),
+            ("ü", "w7w="),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(base64(text.as_bytes()), expected, "{:?}", text);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::przzi_cite::CitationStyle;
+use crate::przzi_keymap::KeymapConfig;
+use crate::przzi_theme::ThemeConfig;
+use crate::PRZZIError;
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Settings read from `config.toml` in the paperazzi config directory, every section is optional
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct UserConfig {
+    pub keys: KeymapConfig,
+    pub theme: ThemeConfig,
+    pub ui: UiConfig,
+}
+
+/// The `[ui]` section of the config file
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    /// Whether the TUI takes the mouse, turn it off to select and copy text the terminal's own way
+    pub mouse: bool,
+    /// Style citations are shown and copied in, until another one is picked
+    pub citation_style: CitationStyle,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        UiConfig {
+            mouse: true,
+            citation_style: CitationStyle::default(),
+        }
+    }
+}
+
+/// Where the config file is looked for, e.g. `~/.config/paperazzi/config.toml` on Linux
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("paperazzi").join(CONFIG_FILE))
+}
+
+/// The user's config, or the defaults when there is no config file
+pub fn load() -> Result<UserConfig, PRZZIError> {
+    let path = match config_path() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(UserConfig::default()),
+    };
+    let contents = fs::read_to_string(&path)?;
+    toml::from_str(&contents).map_err(|e| PRZZIError {
+        msg: format!("Could not read {}: {}", path.display(), e),
+    })
+}
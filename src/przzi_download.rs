@@ -0,0 +1,547 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use reqwest::StatusCode;
+use select::document::Document;
+use select::predicate::{Attr, Name, Predicate};
+use url::Url;
+
+use crate::przzi_api::PRZZIClient;
+use crate::przzi_id::PaperId;
+use crate::PRZZIError;
+
+const SCIHUB_URL: &str = "https://sci-hub.wf/";
+const CHUNK_SIZE: usize = 64 * 1024;
+const PARTIAL_SUFFIX: &str = ".part";
+const PDF_MAGIC: &[u8] = b"%PDF-";
+// The PDF header may be preceded by junk, readers look for it in the first KiB
+const MAGIC_WINDOW: usize = 1024;
+// How much of an HTML page is read to work out what kind of page it is
+const HTML_SNIFF_LIMIT: u64 = 256 * 1024;
+
+// Paywall pages nearly always link to a login too, so only a form that posts to one of
+// these counts as a login page, next to a password field
+const LOGIN_ACTIONS: [&str; 8] = [
+    "login",
+    "log-in",
+    "logon",
+    "signin",
+    "sign-in",
+    "/sso",
+    "saml",
+    "shibboleth",
+];
+const PAYWALL_MARKERS: [&str; 7] = [
+    "purchase",
+    "buy this article",
+    "buy article",
+    "subscribe",
+    "get access",
+    "rent this article",
+    "paywall",
+];
+
+/// Files being downloaded right now, two downloads must never write the same `.part` file
+static DOWNLOADING: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Reasons a download can fail
+#[derive(Debug)]
+pub enum DownloadError {
+    /// sci-hub has no download button for the DOI
+    NoDownloadLink,
+    /// The paper has neither a DOI nor an arXiv ID to download it by
+    NoDownloadSource(PaperId),
+    /// Looking the paper up on Semantic Scholar failed
+    Lookup(String),
+    /// The server answered with a non-success status
+    HttpStatus(StatusCode),
+    /// The server sent something that is neither a PDF nor HTML
+    UnexpectedContentType(String),
+    /// The server sent a login form instead of the PDF
+    LoginPage,
+    /// The server sent a paywall / purchase page instead of the PDF
+    PaywallPage,
+    /// The server sent some other HTML page instead of the PDF
+    HtmlPage,
+    /// The body does not start with the `%PDF-` header
+    NotAPdf,
+    /// The connection closed early, the partial file is kept for resuming
+    Interrupted { downloaded: u64, total: u64 },
+    /// `on_progress` asked to stop, the partial file is kept for resuming
+    Cancelled,
+    /// Another download is already writing to this file
+    InProgress(PathBuf),
+    Request(reqwest::Error),
+    Io(std::io::Error),
+    Url(url::ParseError),
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadError::NoDownloadLink => write!(f, "No download link was found for this DOI"),
+            DownloadError::NoDownloadSource(id) => write!(f, "{} has no DOI or arXiv ID to download it by", id),
+            DownloadError::Lookup(reason) => write!(f, "{}", reason),
+            DownloadError::HttpStatus(status) => write!(f, "Server responded with {}", status),
+            DownloadError::UnexpectedContentType(mime) => {
+                write!(f, "Expected a PDF but the server sent `{}`", mime)
+            }
+            DownloadError::LoginPage => write!(f, "The server asked for a login instead of sending the PDF"),
+            DownloadError::PaywallPage => write!(f, "The paper is behind a paywall"),
+            DownloadError::HtmlPage => write!(f, "The server sent a web page instead of the PDF"),
+            DownloadError::NotAPdf => write!(f, "The downloaded file is not a valid PDF"),
+            DownloadError::Interrupted { downloaded, total } => write!(
+                f,
+                "Download interrupted at {} of {}, run it again to resume",
+                format_bytes(*downloaded),
+                format_bytes(*total)
+            ),
+            DownloadError::Cancelled => write!(f, "Download cancelled"),
+            DownloadError::InProgress(path) => write!(f, "{} is already being downloaded", path.display()),
+            DownloadError::Request(err) => write!(f, "{}", err),
+            DownloadError::Io(err) => write!(f, "{}", err),
+            DownloadError::Url(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(err: reqwest::Error) -> Self {
+        DownloadError::Request(err)
+    }
+}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(err: std::io::Error) -> Self {
+        DownloadError::Io(err)
+    }
+}
+
+impl From<url::ParseError> for DownloadError {
+    fn from(err: url::ParseError) -> Self {
+        DownloadError::Url(err)
+    }
+}
+
+impl From<DownloadError> for PRZZIError {
+    fn from(err: DownloadError) -> Self {
+        PRZZIError {
+            msg: err.to_string(),
+        }
+    }
+}
+
+/// The `type/subtype` part of a Content-Type header, lowercased and without parameters
+pub fn mime_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn has_pdf_magic(head: &[u8]) -> bool {
+    head.windows(PDF_MAGIC.len()).any(|window| window == PDF_MAGIC)
+}
+
+fn looks_like_html(head: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&head[..head.len().min(MAGIC_WINDOW)]).to_ascii_lowercase();
+    let head = head.trim_start();
+    head.starts_with("<!doctype html") || head.starts_with("<html") || head.contains("<head")
+}
+
+/// Works out why an HTML page was served instead of the PDF
+pub fn classify_html(page: &str) -> DownloadError {
+    let lowercase = page.to_ascii_lowercase();
+    if is_login_form(&Document::from(page)) {
+        DownloadError::LoginPage
+    } else if PAYWALL_MARKERS.iter().any(|marker| lowercase.contains(marker)) {
+        DownloadError::PaywallPage
+    } else {
+        DownloadError::HtmlPage
+    }
+}
+
+/// Whether the page asks for a password or has a form that posts to a login endpoint
+fn is_login_form(document: &Document) -> bool {
+    let password_field = document.find(Name("input")).any(|input| {
+        input
+            .attr("type")
+            .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("password"))
+    });
+    password_field
+        || document.find(Name("form")).any(|form| {
+            form.attr("action").is_some_and(|action| {
+                let action = action.to_ascii_lowercase();
+                LOGIN_ACTIONS.iter().any(|marker| action.contains(marker))
+            })
+        })
+}
+
+/// The first byte and the total size of a `Content-Range` header, either may be unknown.
+///
+/// e.g. `bytes 100-199/200` gives `(Some(100), Some(200))` and `bytes */200` gives `(None, Some(200))`
+fn parse_content_range(range: &str) -> (Option<u64>, Option<u64>) {
+    let range = range.trim();
+    let range = range.strip_prefix("bytes").unwrap_or(range).trim_start();
+    let (span, total) = range.split_once('/').unwrap_or((range, "*"));
+    let start = span.split('-').next().and_then(|start| start.trim().parse().ok());
+    (start, total.trim().parse().ok())
+}
+
+fn content_range(res: &reqwest::blocking::Response) -> (Option<u64>, Option<u64>) {
+    res.headers()
+        .get(CONTENT_RANGE)
+        .and_then(|range| range.to_str().ok())
+        .map_or((None, None), parse_content_range)
+}
+
+fn html_error(head: &[u8], res: reqwest::blocking::Response) -> DownloadError {
+    let mut page = head.to_vec();
+    if let Err(err) = res.take(HTML_SNIFF_LIMIT).read_to_end(&mut page) {
+        return err.into();
+    }
+    classify_html(&String::from_utf8_lossy(&page))
+}
+
+/// Reads from `res` until the head holds `MAGIC_WINDOW` bytes or the body ends
+fn read_head(res: &mut reqwest::blocking::Response) -> Result<Vec<u8>, DownloadError> {
+    let mut head = Vec::with_capacity(MAGIC_WINDOW);
+    let mut buf = [0; MAGIC_WINDOW];
+    while head.len() < MAGIC_WINDOW {
+        let read = res.read(&mut buf[..MAGIC_WINDOW - head.len()])?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+    Ok(head)
+}
+
+fn partial_is_pdf(partial: &Path) -> bool {
+    let mut head = Vec::with_capacity(MAGIC_WINDOW);
+    File::open(partial)
+        .and_then(|file| file.take(MAGIC_WINDOW as u64).read_to_end(&mut head))
+        .map(|_| has_pdf_magic(&head))
+        .unwrap_or(false)
+}
+
+/// Holds a file in `DOWNLOADING` until dropped
+struct TargetLock(PathBuf);
+
+impl TargetLock {
+    fn acquire(target: &Path) -> Result<Self, DownloadError> {
+        let mut downloading = DOWNLOADING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if downloading.iter().any(|path| path == target) {
+            return Err(DownloadError::InProgress(target.to_path_buf()));
+        }
+        downloading.push(target.to_path_buf());
+        Ok(TargetLock(target.to_path_buf()))
+    }
+}
+
+impl Drop for TargetLock {
+    fn drop(&mut self) {
+        let mut downloading = DOWNLOADING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        downloading.retain(|path| path != &self.0);
+    }
+}
+
+/// Human readable byte count, e.g. `1.4 MiB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Progress shown next to a running download in the TUI footer
+pub fn format_progress(downloaded: u64, total: Option<u64>) -> String {
+    match total {
+        Some(total) if total > 0 => format!(
+            "{} / {} ({}%)",
+            format_bytes(downloaded),
+            format_bytes(total),
+            downloaded * 100 / total
+        ),
+        _ => format_bytes(downloaded),
+    }
+}
+
+/// The sci-hub page for a DOI, e.g. `10.1109/ICCV.2017.405`
+pub fn scihub_url(doi: &str) -> Result<Url, DownloadError> {
+    Ok(Url::parse(SCIHUB_URL)?.join(doi.trim_start_matches('/'))?)
+}
+
+/// Downloads the paper behind any identifier.
+///
+/// DOIs go through sci-hub and arXiv IDs straight to arXiv, other IDs are first
+/// looked up on Semantic Scholar to find one of those.
+pub fn download_id<F>(id: &PaperId, on_progress: F) -> Result<PathBuf, DownloadError>
+where
+    F: FnMut(u64, Option<u64>) -> bool,
+{
+    let client = reqwest::blocking::Client::new();
+    let id = match id {
+        PaperId::Doi(_) | PaperId::ArXiv { .. } => id.clone(),
+        _ => resolve_download_id(id)?,
+    };
+    if let Some(doi) = id.doi() {
+        return download_doi(scihub_url(doi)?, on_progress);
+    }
+    match id.arxiv_pdf_url() {
+        Some(pdf_url) => download_pdf(&client, Url::parse(&pdf_url)?, on_progress),
+        None => Err(DownloadError::NoDownloadSource(id)),
+    }
+}
+
+/// Finds the DOI or arXiv ID of a paper known by another identifier
+fn resolve_download_id(id: &PaperId) -> Result<PaperId, DownloadError> {
+    let paper = PRZZIClient::new()
+        .paper(id)
+        .map_err(|e| DownloadError::Lookup(e.msg))?;
+    paper
+        .doi
+        .and_then(|doi| doi.parse().ok())
+        .or_else(|| paper.arxiv.and_then(|arxiv| arxiv.parse().ok()))
+        .ok_or_else(|| DownloadError::NoDownloadSource(id.clone()))
+}
+
+/// Finds the PDF link on the sci-hub page for the DOI and downloads it.
+///
+/// `on_progress` is called with the number of bytes on disk and the total size, when known,
+/// and stops the download by returning `false`.
+pub fn download_doi<F>(doi_url: Url, on_progress: F) -> Result<PathBuf, DownloadError>
+where
+    F: FnMut(u64, Option<u64>) -> bool,
+{
+    let client = reqwest::blocking::Client::new();
+    let res = client.get(doi_url.clone()).send()?;
+    let document = Document::from(res.text()?.as_str());
+    // Need better way to do this
+    let link = document
+        .find(Attr("id", "buttons").descendant(Name("button")))
+        .next()
+        .and_then(|button| button.attr("onclick"))
+        .ok_or(DownloadError::NoDownloadLink)?
+        .replace("location.href='", "")
+        .replace('\'', "");
+    let down_url = doi_url.to_string();
+    let down_url = Url::parse(down_url.split("https://doi.org").next().unwrap())?;
+    let down_url = down_url.join(&link)?;
+    download_pdf(&client, down_url, on_progress)
+}
+
+/// Streams the PDF at `pdf_url` into `<name>.part` and renames it once complete.
+///
+/// If a `.part` file is already present the download is resumed with a `Range` request,
+/// it is only kept when the server resumes at its end or reports it as the full size.
+/// Nothing is written until the response is known to be a PDF, and a download of a file
+/// that is already being downloaded fails with `DownloadError::InProgress`.
+pub fn download_pdf<F>(
+    client: &reqwest::blocking::Client,
+    pdf_url: Url,
+    mut on_progress: F,
+) -> Result<PathBuf, DownloadError>
+where
+    F: FnMut(u64, Option<u64>) -> bool,
+{
+    let filename = pdf_url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("paper.pdf")
+        .to_string();
+    let target = PathBuf::from(&filename);
+    let partial = PathBuf::from(format!("{}{}", filename, PARTIAL_SUFFIX));
+    let _lock = TargetLock::acquire(&target)?;
+
+    let (mut res, resume_from) = loop {
+        let mut resume_from = fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+        if resume_from > 0 && !partial_is_pdf(&partial) {
+            // Left behind by something else, start over
+            fs::remove_file(&partial)?;
+            resume_from = 0;
+        }
+        let mut request = client.get(pdf_url.clone());
+        if resume_from > 0 {
+            request = request.header(RANGE, format!("bytes={}-", resume_from));
+        }
+        let res = request.send()?;
+        if resume_from == 0 {
+            break (res, resume_from);
+        }
+        match res.status() {
+            StatusCode::RANGE_NOT_SATISFIABLE => {
+                // Only a partial file as large as the whole document is complete,
+                // a truncated one still starts with the PDF header
+                if content_range(&res).1 == Some(resume_from) {
+                    fs::rename(&partial, &target)?;
+                    // Nothing left to cancel
+                    let _ = on_progress(resume_from, Some(resume_from));
+                    return Ok(target);
+                }
+            }
+            StatusCode::PARTIAL_CONTENT => {
+                if content_range(&res).0 == Some(resume_from) {
+                    break (res, resume_from);
+                }
+            }
+            // The server ignored the range and sends the whole file
+            _ => break (res, resume_from),
+        }
+        // The partial file does not line up with what the server has, start over
+        fs::remove_file(&partial)?;
+    };
+    if !res.status().is_success() {
+        return Err(DownloadError::HttpStatus(res.status()));
+    }
+    let mime = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .map(mime_type)
+        .filter(|mime| !mime.is_empty());
+    let resumed = resume_from > 0 && res.status() == StatusCode::PARTIAL_CONTENT;
+    let total = if resumed {
+        content_range(&res).1
+    } else {
+        res.headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse::<u64>().ok())
+    };
+
+    let head = if resumed { Vec::new() } else { read_head(&mut res)? };
+    match mime.as_deref() {
+        Some("text/html") | Some("application/xhtml+xml") => return Err(html_error(&head, res)),
+        // Missing or generic types are judged by the magic bytes alone
+        None | Some("application/pdf") | Some("application/x-pdf") | Some("application/octet-stream") => {}
+        Some(other) => return Err(DownloadError::UnexpectedContentType(other.to_string())),
+    }
+    if !resumed && !has_pdf_magic(&head) {
+        if looks_like_html(&head) {
+            return Err(html_error(&head, res));
+        }
+        return Err(DownloadError::NotAPdf);
+    }
+
+    let mut downloaded = if resumed { resume_from } else { 0 };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&partial)?;
+    file.write_all(&head)?;
+    downloaded += head.len() as u64;
+    if !on_progress(downloaded, total) {
+        return Err(DownloadError::Cancelled);
+    }
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let read = res.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buf[..read])?;
+        downloaded += read as u64;
+        if !on_progress(downloaded, total) {
+            return Err(DownloadError::Cancelled);
+        }
+    }
+    file.flush()?;
+    drop(file);
+
+    if let Some(total) = total {
+        if downloaded < total {
+            // Keep the partial file around so the next attempt can resume
+            return Err(DownloadError::Interrupted { downloaded, total });
+        }
+    }
+    fs::rename(&partial, &target)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_html_pages() {
+        let paywall = "<html><header><a href=\"/signin\">Sign in</a> | Log in via your institution</header>\
+            <main>Purchase PDF for $39.95 <a>Get access</a></main></html>";
+        assert!(matches!(classify_html(paywall), DownloadError::PaywallPage));
+
+        let password = "<html><body>Sign in<form><input type=\"text\" name=\"user\">\
+            <input type=\"Password\" name=\"pass\"></form></body></html>";
+        assert!(matches!(classify_html(password), DownloadError::LoginPage));
+
+        let sso = "<html><body><form action=\"https://idp.example.edu/idp/profile/SAML2/Redirect/SSO\">\
+            <button>Continue</button></form> Subscribe now</body></html>";
+        assert!(matches!(classify_html(sso), DownloadError::LoginPage));
+
+        let search = "<html><body><form action=\"/search\"><input type=\"text\"></form></body></html>";
+        assert!(matches!(classify_html(search), DownloadError::HtmlPage));
+        assert!(matches!(classify_html(""), DownloadError::HtmlPage));
+    }
+
+    #[test]
+    fn mime_types() {
+        let cases = [
+            ("application/pdf", "application/pdf"),
+            ("Application/PDF; charset=binary", "application/pdf"),
+            ("  text/html ;charset=utf-8", "text/html"),
+            ("", ""),
+        ];
+        for (content_type, expected) in cases {
+            assert_eq!(mime_type(content_type), expected, "{:?}", content_type);
+        }
+    }
+
+    #[test]
+    fn pdf_magic() {
+        assert!(has_pdf_magic(b"%PDF-1.7\n%\xe2\xe3"));
+        assert!(has_pdf_magic(b"\xef\xbb\xbf\r\n%PDF-1.4"));
+        assert!(!has_pdf_magic(b"<!DOCTYPE html><html>"));
+        assert!(!has_pdf_magic(b"%PDF"));
+        assert!(!has_pdf_magic(b""));
+    }
+
+    #[test]
+    fn one_download_per_file() {
+        let lock = TargetLock::acquire(Path::new("locked.pdf")).unwrap();
+        assert!(matches!(
+            TargetLock::acquire(Path::new("locked.pdf")),
+            Err(DownloadError::InProgress(_))
+        ));
+        assert!(TargetLock::acquire(Path::new("other.pdf")).is_ok());
+        drop(lock);
+        assert!(TargetLock::acquire(Path::new("locked.pdf")).is_ok());
+    }
+
+    #[test]
+    fn content_ranges() {
+        let cases = [
+            ("bytes 100-199/200", (Some(100), Some(200))),
+            ("bytes 0-99/*", (Some(0), None)),
+            ("bytes */4096", (None, Some(4096))),
+            ("garbage", (None, None)),
+        ];
+        for (range, expected) in cases {
+            assert_eq!(parse_content_range(range), expected, "{:?}", range);
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::{ArgEnum, Args};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+
+use crate::przzi_api::PRZZIClient;
+use crate::przzi_id::PaperId;
+use crate::{PRZZIError, PRZZIResult};
+
+/// Crawl the citation network around some papers and export it
+#[derive(Args)]
+pub struct GraphConfig {
+    /// papers to start from (DOI, arXiv, PubMed or Semantic Scholar ID)
+    #[clap(short = 's', long = "seed", required = true, multiple_occurrences = true)]
+    pub seed: Vec<PaperId>,
+
+    /// how many hops to follow away from the seeds
+    #[clap(long = "depth", default_value_t = 1)]
+    pub depth: usize,
+
+    /// which links to follow
+    #[clap(long = "direction", arg_enum, default_value = "both")]
+    pub direction: GraphDirection,
+
+    /// stop once the graph has this many papers
+    #[clap(long = "max-nodes", default_value_t = 200)]
+    pub max_nodes: usize,
+
+    /// stop after this many API requests
+    #[clap(long = "max-requests", default_value_t = 100)]
+    pub max_requests: usize,
+
+    /// references / citations fetched per paper
+    #[clap(long = "per-paper", default_value_t = 50)]
+    pub per_paper: usize,
+
+    /// minimum time between two API requests, in milliseconds
+    #[clap(long = "delay", default_value_t = 1000)]
+    pub delay_ms: u64,
+
+    /// output format, guessed from the output file extension when left out
+    #[clap(short = 'f', long = "format", arg_enum)]
+    pub format: Option<GraphFormat>,
+
+    /// file to write the graph to, printed to stdout when left out
+    #[clap(short = 'o', long = "output")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq)]
+pub enum GraphDirection {
+    References,
+    Citations,
+    Both,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Graphml,
+    Dot,
+    Json,
+}
+
+#[derive(Serialize)]
+pub struct GraphNode {
+    /// Hops away from the nearest seed
+    pub depth: usize,
+    #[serde(flatten)]
+    pub paper: PRZZIResult,
+}
+
+/// `source` cites `target`
+#[derive(Serialize, PartialEq, Eq, Hash)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+}
+
+#[derive(Serialize)]
+pub struct CitationGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    /// Why the crawl stopped early, if it did
+    pub truncated: Option<String>,
+}
+
+/// Paces requests and enforces the request budget
+struct RateLimiter {
+    delay: Duration,
+    last: Option<Instant>,
+    made: usize,
+    max: usize,
+}
+
+impl RateLimiter {
+    /// Waits for the next request slot, or returns false when the budget is spent
+    fn acquire(&mut self) -> bool {
+        if self.made >= self.max {
+            return false;
+        }
+        if let Some(last) = self.last {
+            let elapsed = last.elapsed();
+            if elapsed < self.delay {
+                thread::sleep(self.delay - elapsed);
+            }
+        }
+        self.last = Some(Instant::now());
+        self.made += 1;
+        true
+    }
+}
+
+struct Crawler<'a> {
+    client: &'a PRZZIClient,
+    limiter: RateLimiter,
+    max_nodes: usize,
+    index: HashMap<String, usize>,
+    nodes: Vec<GraphNode>,
+    edges: HashSet<GraphEdge>,
+    edge_order: Vec<(String, String)>,
+}
+
+impl Crawler<'_> {
+    /// Adds a paper unless it is already known.
+    ///
+    /// Returns its ID and whether it is new, or `None` when it has no ID or the node limit is hit.
+    fn add_node(&mut self, paper: PRZZIResult, depth: usize) -> Option<(String, bool)> {
+        let paper_id = paper.paper_id.clone()?;
+        if self.index.contains_key(&paper_id) {
+            return Some((paper_id, false));
+        }
+        if self.nodes.len() >= self.max_nodes {
+            return None;
+        }
+        self.index.insert(paper_id.clone(), self.nodes.len());
+        self.nodes.push(GraphNode { depth, paper });
+        Some((paper_id, true))
+    }
+
+    fn add_edge(&mut self, source: &str, target: &str) {
+        let edge = GraphEdge {
+            source: source.to_string(),
+            target: target.to_string(),
+        };
+        if self.edges.insert(edge) {
+            self.edge_order.push((source.to_string(), target.to_string()));
+        }
+    }
+
+    fn at_node_limit(&self) -> bool {
+        self.nodes.len() >= self.max_nodes
+    }
+}
+
+/// Breadth-first crawl from the seeds, stopping at the depth, node or request limits
+pub fn crawl(client: &PRZZIClient, config: &GraphConfig) -> Result<CitationGraph, PRZZIError> {
+    let mut crawler = Crawler {
+        client,
+        limiter: RateLimiter {
+            delay: Duration::from_millis(config.delay_ms),
+            last: None,
+            made: 0,
+            max: config.max_requests,
+        },
+        max_nodes: config.max_nodes,
+        index: HashMap::new(),
+        nodes: Vec::new(),
+        edges: HashSet::new(),
+        edge_order: Vec::new(),
+    };
+    let progress = ProgressBar::new_spinner();
+    progress.set_style(ProgressStyle::with_template("{spinner} [{elapsed_precise}] {msg}").unwrap());
+
+    let mut queue = VecDeque::new();
+    let mut truncated = None;
+    for seed in &config.seed {
+        if !crawler.limiter.acquire() {
+            truncated = Some(format!("request limit of {} reached", config.max_requests));
+            break;
+        }
+        let paper = crawler.client.paper(seed)?;
+        match crawler.add_node(paper, 0) {
+            Some((paper_id, true)) => queue.push_back((paper_id, 0)),
+            None if crawler.at_node_limit() => {
+                truncated = Some(format!("node limit of {} reached", config.max_nodes));
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let mut directions = Vec::new();
+    if config.direction != GraphDirection::Citations {
+        directions.push(GraphDirection::References);
+    }
+    if config.direction != GraphDirection::References {
+        directions.push(GraphDirection::Citations);
+    }
+
+    'crawl: while let Some((paper_id, depth)) = queue.pop_front() {
+        if truncated.is_some() {
+            break;
+        }
+        if depth >= config.depth {
+            continue;
+        }
+        for direction in &directions {
+            progress.set_message(format!(
+                "{} papers, {} links, {} requests",
+                crawler.nodes.len(),
+                crawler.edge_order.len(),
+                crawler.limiter.made
+            ));
+            if !crawler.limiter.acquire() {
+                truncated = Some(format!("request limit of {} reached", config.max_requests));
+                break 'crawl;
+            }
+            let fetched = match direction {
+                GraphDirection::References => crawler.client.references(&paper_id, config.per_paper),
+                _ => crawler.client.citations(&paper_id, config.per_paper),
+            };
+            let linked = match fetched {
+                Ok(linked) => linked,
+                Err(e) => {
+                    // Usually rate limiting, keep what was crawled so far
+                    truncated = Some(format!("request failed: {}", e.msg));
+                    break 'crawl;
+                }
+            };
+            for paper in linked {
+                let (linked_id, is_new) = match crawler.add_node(paper, depth + 1) {
+                    Some(added) => added,
+                    None if crawler.at_node_limit() => {
+                        truncated = Some(format!("node limit of {} reached", config.max_nodes));
+                        break 'crawl;
+                    }
+                    None => continue,
+                };
+                if *direction == GraphDirection::References {
+                    crawler.add_edge(&paper_id, &linked_id);
+                } else {
+                    crawler.add_edge(&linked_id, &paper_id);
+                }
+                if is_new {
+                    queue.push_back((linked_id, depth + 1));
+                }
+            }
+        }
+    }
+    progress.finish_and_clear();
+
+    let edges = crawler
+        .edge_order
+        .into_iter()
+        .map(|(source, target)| GraphEdge { source, target })
+        .collect();
+    Ok(CitationGraph {
+        nodes: crawler.nodes,
+        edges,
+        truncated,
+    })
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn node_authors(node: &GraphNode) -> String {
+    node.paper
+        .authors
+        .iter()
+        .map(|author| author.name.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+pub fn to_graphml(graph: &CitationGraph) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    let keys = [
+        ("title", "string"),
+        ("year", "int"),
+        ("venue", "string"),
+        ("authors", "string"),
+        ("citations", "int"),
+        ("doi", "string"),
+        ("url", "string"),
+        ("depth", "int"),
+    ];
+    for (name, kind) in keys {
+        out.push_str(&format!(
+            "  <key id=\"{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"{1}\"/>\n",
+            name, kind
+        ));
+    }
+    out.push_str("  <graph id=\"citations\" edgedefault=\"directed\">\n");
+    for node in &graph.nodes {
+        let paper = &node.paper;
+        out.push_str(&format!(
+            "    <node id=\"{}\">\n",
+            xml_escape(paper.paper_id.as_deref().unwrap_or(""))
+        ));
+        let mut data = vec![
+            ("title", paper.title.clone()),
+            ("venue", paper.venue.clone()),
+            ("authors", node_authors(node)),
+            ("url", paper.url.to_string()),
+            ("depth", node.depth.to_string()),
+        ];
+        if let Some(year) = paper.year {
+            data.push(("year", year.to_string()));
+        }
+        if let Some(citations) = paper.citation_count {
+            data.push(("citations", citations.to_string()));
+        }
+        if let Some(doi) = &paper.doi {
+            data.push(("doi", doi.clone()));
+        }
+        for (key, value) in data {
+            out.push_str(&format!("      <data key=\"{}\">{}</data>\n", key, xml_escape(&value)));
+        }
+        out.push_str("    </node>\n");
+    }
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\"/>\n",
+            xml_escape(&edge.source),
+            xml_escape(&edge.target)
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+pub fn to_dot(graph: &CitationGraph) -> String {
+    let mut out = String::from("digraph citations {\n  node [shape=box];\n");
+    for node in &graph.nodes {
+        let paper = &node.paper;
+        let label = match paper.year {
+            Some(year) => format!("{} ({})", paper.title, year),
+            None => paper.title.clone(),
+        };
+        out.push_str(&format!(
+            "  \"{}\" [label=\"{}\", authors=\"{}\", citations={}, depth={}, URL=\"{}\"];\n",
+            dot_escape(paper.paper_id.as_deref().unwrap_or("")),
+            dot_escape(&label),
+            dot_escape(&node_authors(node)),
+            paper.citation_count.unwrap_or(0),
+            node.depth,
+            dot_escape(paper.url.as_str())
+        ));
+    }
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "  \"{}\" -> \"{}\";\n",
+            dot_escape(&edge.source),
+            dot_escape(&edge.target)
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Crawls the graph and writes it out in the requested format
+pub fn run(client: &PRZZIClient, config: &GraphConfig) -> Result<CitationGraph, PRZZIError> {
+    let graph = crawl(client, config)?;
+    let format = config.format.unwrap_or_else(|| {
+        let extension = config
+            .output
+            .as_ref()
+            .and_then(|output| output.extension())
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("graphml") | Some("xml") => GraphFormat::Graphml,
+            Some("dot") | Some("gv") => GraphFormat::Dot,
+            _ => GraphFormat::Json,
+        }
+    });
+    let rendered = match format {
+        GraphFormat::Graphml => to_graphml(&graph),
+        GraphFormat::Dot => to_dot(&graph),
+        GraphFormat::Json => serde_json::to_string_pretty(&graph)?,
+    };
+    match &config.output {
+        Some(output) => fs::write(output, rendered)?,
+        None => println!("{}", rendered),
+    }
+    Ok(graph)
+}
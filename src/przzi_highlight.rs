@@ -0,0 +1,300 @@
+use tui::style::Style;
+use tui::text::Span;
+
+/// Words left out of the query, they would light up every abstract
+const STOP_WORDS: [&str; 24] = [
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "into", "is", "it", "of", "on", "or",
+    "that", "the", "to", "via", "was", "with", "using",
+];
+
+/// Endings stripped so `networks`, `learning` and `trained` match `network`, `learn` and `train`,
+/// longest first
+const SUFFIXES: [&str; 18] = [
+    "ational", "ization", "ations", "ation", "ments", "ment", "ness", "ings", "ing", "ies", "ied", "ers", "er",
+    "ed", "es", "ly", "s", "e",
+];
+
+/// Shortest stem a suffix may be stripped down to
+const MIN_STEM: usize = 3;
+
+/// Crude stemmer, good enough to tell that two words are forms of each other
+pub fn stem(word: &str) -> String {
+    let word = word.to_lowercase();
+    // `class` is not the plural of `clas`
+    if word.ends_with("ss") {
+        return word;
+    }
+    for suffix in SUFFIXES {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem.chars().count() >= MIN_STEM {
+                return stem.trim_end_matches('y').to_string();
+            }
+        }
+    }
+    word.strip_suffix('y')
+        .filter(|stem| stem.chars().count() >= MIN_STEM)
+        .unwrap_or(&word)
+        .to_string()
+}
+
+/// The stems of the words in a query, without stop words or repeats
+pub fn query_stems(query: &str) -> Vec<String> {
+    let mut stems: Vec<String> = Vec::new();
+    for word in query.split(|c: char| !c.is_alphanumeric()) {
+        let lower = word.to_lowercase();
+        if lower.is_empty() || STOP_WORDS.contains(&lower.as_str()) {
+            continue;
+        }
+        let stem = stem(&lower);
+        if !stems.contains(&stem) {
+            stems.push(stem);
+        }
+    }
+    stems
+}
+
+/// Splits text into words and the separators between them
+fn tokens(text: &str) -> Vec<(&str, bool)> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_word = false;
+    for (at, c) in text.char_indices() {
+        if c.is_alphanumeric() != in_word {
+            if at > start {
+                tokens.push((&text[start..at], in_word));
+            }
+            start = at;
+            in_word = !in_word;
+        }
+    }
+    if start < text.len() {
+        tokens.push((&text[start..], in_word));
+    }
+    tokens
+}
+
+fn is_match(word: &str, stems: &[String]) -> bool {
+    !stems.is_empty() && stems.contains(&stem(word))
+}
+
+/// Number of words in `text` matching the query
+pub fn count_matches(text: &str, stems: &[String]) -> usize {
+    tokens(text)
+        .into_iter()
+        .filter(|(token, is_word)| *is_word && is_match(token, stems))
+        .count()
+}
+
+/// `text` as spans with the words matching the query in `highlight`.
+///
+/// `counter` numbers the matches across calls so the match number `current` can be drawn in `current_style`.
+pub fn highlight<'a>(
+    text: &'a str,
+    stems: &[String],
+    style: Style,
+    highlight: Style,
+    current: Option<(usize, Style)>,
+    counter: &mut usize,
+) -> Vec<Span<'a>> {
+    let mut spans: Vec<Span<'a>> = Vec::new();
+    let mut plain_start: Option<usize> = None;
+    let mut offset = 0;
+    for (token, is_word) in tokens(text) {
+        if is_word && is_match(token, stems) {
+            if let Some(start) = plain_start.take() {
+                spans.push(Span::styled(&text[start..offset], style));
+            }
+            let token_style = match current {
+                Some((index, current_style)) if index == *counter => current_style,
+                _ => highlight,
+            };
+            spans.push(Span::styled(token, token_style));
+            *counter += 1;
+        } else if plain_start.is_none() {
+            plain_start = Some(offset);
+        }
+        offset += token.len();
+    }
+    if let Some(start) = plain_start {
+        spans.push(Span::styled(&text[start..], style));
+    }
+    spans
+}
+
+/// Breaks text into lines of at most `width` characters between words, so it is known which line a match ends up on
+pub fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word;
+        // Words longer than a line are broken up
+        while word.chars().count() > width {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let split = word.char_indices().nth(width).map_or(word.len(), |(at, _)| at);
+            lines.push(word[..split].to_string());
+            word = &word[split..];
+        }
+        if word.is_empty() {
+            continue;
+        }
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// A piece of found text within one line, an occurrence broken over two lines has a piece on each
+pub struct Found {
+    pub line: usize,
+    /// Byte range in the line
+    pub start: usize,
+    pub end: usize,
+    /// Which occurrence, counting from the start of the text
+    pub occurrence: usize,
+}
+
+/// Every case-insensitive occurrence of `needle` in lines made by `wrap_words`
+pub fn find_in_lines(lines: &[String], needle: &str) -> Vec<Found> {
+    let needle: Vec<char> = needle
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    // `wrap_words` put single spaces between words, so joining the lines gives back the text
+    let text = lines.join(" ");
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut line_starts: Vec<usize> = Vec::new();
+    let mut offset = 0;
+    for line in lines {
+        line_starts.push(offset);
+        offset += line.len() + 1;
+    }
+
+    let mut found: Vec<Found> = Vec::new();
+    let mut occurrence = 0;
+    let mut at = 0;
+    while at < chars.len() {
+        let mut lower = chars[at..].iter().flat_map(|(_, c)| c.to_lowercase());
+        if !needle.iter().all(|n| lower.next() == Some(*n)) {
+            at += 1;
+            continue;
+        }
+        // Lowercasing can turn one character into several, find where the match ends in the text
+        let mut end_char = at;
+        let mut lowered = 0;
+        while lowered < needle.len() && end_char < chars.len() {
+            lowered += chars[end_char].1.to_lowercase().count();
+            end_char += 1;
+        }
+        let start = chars[at].0;
+        let end = chars.get(end_char).map_or(text.len(), |(byte, _)| *byte);
+        for (line, line_start) in line_starts.iter().enumerate() {
+            let line_end = line_start + lines[line].len();
+            if start < line_end && end > *line_start {
+                found.push(Found {
+                    line,
+                    start: start.max(*line_start) - line_start,
+                    end: end.min(line_end) - line_start,
+                    occurrence,
+                });
+            }
+        }
+        occurrence += 1;
+        at = end_char.max(at + 1);
+    }
+    found
+}
+
+/// `line` as spans with the found pieces on that line in `highlight`, occurrence number `current` in `current_style`
+pub fn mark_found<'a>(
+    line: &'a str,
+    line_index: usize,
+    found: &[Found],
+    style: Style,
+    highlight: Style,
+    current: (usize, Style),
+) -> Vec<Span<'a>> {
+    let mut spans: Vec<Span<'a>> = Vec::new();
+    let mut offset = 0;
+    for piece in found.iter().filter(|piece| piece.line == line_index) {
+        if piece.start > offset {
+            spans.push(Span::styled(&line[offset..piece.start], style));
+        }
+        let piece_style = if piece.occurrence == current.0 { current.1 } else { highlight };
+        spans.push(Span::styled(&line[piece.start..piece.end], piece_style));
+        offset = piece.end;
+    }
+    if offset < line.len() {
+        spans.push(Span::styled(&line[offset..], style));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps() {
+        let cases: [(&str, usize, &[&str]); 6] = [
+            ("", 10, &[]),
+            ("attention is all you need", 10, &["attention", "is all you", "need"]),
+            ("attention is all you need", 100, &["attention is all you need"]),
+            ("  spread   out\nwords ", 20, &["spread out words"]),
+            ("a transformerless model", 5, &["a", "trans", "forme", "rless", "model"]),
+            ("naïve façade", 6, &["naïve", "façade"]),
+        ];
+        for (text, width, expected) in cases {
+            assert_eq!(wrap_words(text, width), expected, "{:?} at {}", text, width);
+        }
+        assert_eq!(wrap_words("ab", 0), ["a", "b"]);
+    }
+
+    fn pieces(found: &[Found]) -> Vec<(usize, usize, usize, usize)> {
+        found
+            .iter()
+            .map(|piece| (piece.line, piece.start, piece.end, piece.occurrence))
+            .collect()
+    }
+
+    #[test]
+    fn finds() {
+        let lines = wrap_words("Attention is all you need, attention!", 10);
+        assert_eq!(lines, ["Attention", "is all you", "need,", "attention!"]);
+        assert_eq!(pieces(&find_in_lines(&lines, "ATTENTION")), [(0, 0, 9, 0), (3, 0, 9, 1)]);
+        // A match over a line break has a piece on each line
+        assert_eq!(
+            pieces(&find_in_lines(&lines, "you  need")),
+            [(1, 7, 10, 0), (2, 0, 4, 0)]
+        );
+        assert!(find_in_lines(&lines, "   ").is_empty());
+        assert!(find_in_lines(&lines, "transformer").is_empty());
+        assert!(find_in_lines(&[], "a").is_empty());
+    }
+
+    #[test]
+    fn finds_overlapping_and_unicode() {
+        let lines = vec!["aaa".to_string()];
+        assert_eq!(pieces(&find_in_lines(&lines, "aa")), [(0, 0, 2, 0)]);
+        let lines = vec!["Straße İstanbul".to_string()];
+        assert_eq!(pieces(&find_in_lines(&lines, "straße")), [(0, 0, 7, 0)]);
+        // `İ` lowercases to two characters
+        assert_eq!(pieces(&find_in_lines(&lines, "i̇stanbul")), [(0, 8, 17, 0)]);
+    }
+}
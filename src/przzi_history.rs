@@ -0,0 +1,112 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local, Utc};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+use crate::przzi_api;
+use crate::{PRZZIError, SearchFilters};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// List past searches or run one of them again
+#[derive(Args)]
+pub struct HistoryConfig {
+    /// run the search with this number from the list again
+    #[clap(long = "run")]
+    pub run: Option<usize>,
+
+    /// number of recent searches to list
+    #[clap(short = 'n', long = "limit", default_value_t = 20)]
+    pub limit: usize,
+}
+
+/// A search as saved in the history file
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub provider: String,
+    pub query: String,
+    #[serde(default)]
+    pub filters: SearchFilters,
+    pub num_results: usize,
+    pub result_count: usize,
+}
+
+impl HistoryEntry {
+    pub fn new(query: String, filters: SearchFilters, num_results: usize, result_count: usize) -> Self {
+        HistoryEntry {
+            timestamp: Utc::now(),
+            provider: przzi_api::PROVIDER.to_string(),
+            query,
+            filters,
+            num_results,
+            result_count,
+        }
+    }
+
+    pub fn local_time(&self) -> String {
+        local_time(&self.timestamp)
+    }
+}
+
+/// A time in the local timezone, with the weekday, e.g. `Tue 2024-03-12 14:05`
+pub fn local_time(time: &DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%a %Y-%m-%d %H:%M").to_string()
+}
+
+fn history_path() -> Result<PathBuf, PRZZIError> {
+    Ok(crate::data_dir()?.join(HISTORY_FILE))
+}
+
+/// Every saved search, oldest first
+pub fn load() -> Result<Vec<HistoryEntry>, PRZZIError> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(path)?;
+    // A line that cant be read (e.g. cut short by a crash) shouldnt lose the rest
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Appends a search to the history file
+pub fn record(entry: &HistoryEntry) -> Result<(), PRZZIError> {
+    let mut file = OpenOptions::new().create(true).append(true).open(history_path()?)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// The last `limit` searches as a table, numbered for `paperazzi history --run`
+pub fn format_list(entries: &[HistoryEntry], limit: usize) -> String {
+    if entries.is_empty() {
+        return "No searches saved yet".to_string();
+    }
+    let skip = entries.len().saturating_sub(limit);
+    entries
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .map(|(index, entry)| {
+            let mut line = format!(
+                "{:>4}  {}  {:<16} {:>3} results  \"{}\"",
+                index + 1,
+                entry.local_time(),
+                entry.provider,
+                entry.result_count,
+                entry.query
+            );
+            let filters = entry.filters.describe();
+            if !filters.is_empty() {
+                line.push_str(&format!("  ({})", filters));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
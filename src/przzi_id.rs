@@ -0,0 +1,313 @@
+use std::fmt;
+use std::str::FromStr;
+
+use percent_encoding::percent_decode_str;
+
+// Links are percent-encoded, a DOI written out as `doi:` may contain a literal `%`
+const DOI_URL_PREFIXES: [&str; 6] = [
+    "https://doi.org/",
+    "http://doi.org/",
+    "https://dx.doi.org/",
+    "http://dx.doi.org/",
+    "doi.org/",
+    "dx.doi.org/",
+];
+const DOI_PREFIXES: [&str; 2] = ["urn:doi:", "doi:"];
+const ARXIV_PREFIXES: [&str; 7] = [
+    "https://arxiv.org/abs/",
+    "http://arxiv.org/abs/",
+    "https://arxiv.org/pdf/",
+    "http://arxiv.org/pdf/",
+    "arxiv.org/abs/",
+    "arxiv.org/pdf/",
+    "arxiv:",
+];
+const PUBMED_PREFIXES: [&str; 5] = [
+    "https://pubmed.ncbi.nlm.nih.gov/",
+    "http://pubmed.ncbi.nlm.nih.gov/",
+    "pubmed.ncbi.nlm.nih.gov/",
+    "pubmed:",
+    "pmid:",
+];
+const S2_PREFIXES: [&str; 7] = [
+    "https://www.semanticscholar.org/paper/",
+    "https://semanticscholar.org/paper/",
+    "http://www.semanticscholar.org/paper/",
+    "http://semanticscholar.org/paper/",
+    "www.semanticscholar.org/paper/",
+    "semanticscholar.org/paper/",
+    "s2:",
+];
+const CORPUS_PREFIX: &str = "corpusid:";
+
+/// A paper identifier given on the command line or in a reading list
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PaperId {
+    /// Lowercased DOI without any prefix, e.g. `10.1109/iccv.2017.405`
+    Doi(String),
+    /// New (`1706.03762`) or old (`hep-th/9901001`) style arXiv ID
+    ArXiv { id: String, version: Option<u32> },
+    /// PubMed ID
+    PubMed(String),
+    /// Semantic Scholar paper ID (40 hex characters)
+    SemanticScholar(String),
+    /// Semantic Scholar corpus ID
+    CorpusId(String),
+}
+
+impl PaperId {
+    /// The ID as accepted by the Semantic Scholar `/paper/{id}` endpoints
+    pub fn to_s2_id(&self) -> String {
+        match self {
+            PaperId::Doi(doi) => format!("DOI:{}", doi),
+            // Semantic Scholar does not track arXiv versions
+            PaperId::ArXiv { id, .. } => format!("ARXIV:{}", id),
+            PaperId::PubMed(pmid) => format!("PMID:{}", pmid),
+            PaperId::SemanticScholar(sha) => sha.clone(),
+            PaperId::CorpusId(corpus_id) => format!("CorpusId:{}", corpus_id),
+        }
+    }
+
+    pub fn doi(&self) -> Option<&str> {
+        match self {
+            PaperId::Doi(doi) => Some(doi),
+            _ => None,
+        }
+    }
+
+    /// Link to the arXiv PDF, keeping the version when one was given
+    pub fn arxiv_pdf_url(&self) -> Option<String> {
+        match self {
+            PaperId::ArXiv { id, version: Some(version) } => {
+                Some(format!("https://arxiv.org/pdf/{}v{}.pdf", id, version))
+            }
+            PaperId::ArXiv { id, version: None } => Some(format!("https://arxiv.org/pdf/{}.pdf", id)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PaperId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaperId::Doi(doi) => write!(f, "{}", doi),
+            PaperId::ArXiv { id, version: Some(version) } => write!(f, "arXiv:{}v{}", id, version),
+            PaperId::ArXiv { id, version: None } => write!(f, "arXiv:{}", id),
+            PaperId::PubMed(pmid) => write!(f, "PMID:{}", pmid),
+            PaperId::SemanticScholar(sha) => write!(f, "{}", sha),
+            PaperId::CorpusId(corpus_id) => write!(f, "CorpusId:{}", corpus_id),
+        }
+    }
+}
+
+impl FromStr for PaperId {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let trimmed = input.trim().trim_matches(|c| c == '<' || c == '>');
+        let lower = trimmed.to_ascii_lowercase();
+        let unrecognised = || {
+            format!(
+                "`{}` is not a DOI, arXiv ID, PubMed ID (pmid:...) or Semantic Scholar ID",
+                input.trim()
+            )
+        };
+
+        if let Some(rest) = strip_any_prefix(trimmed, &lower, &DOI_URL_PREFIXES) {
+            let decoded = percent_decode_str(rest).decode_utf8_lossy();
+            return parse_doi(&decoded).ok_or_else(unrecognised);
+        }
+        if let Some(rest) = strip_any_prefix(trimmed, &lower, &DOI_PREFIXES) {
+            return parse_doi(rest).ok_or_else(unrecognised);
+        }
+        if let Some(rest) = strip_any_prefix(trimmed, &lower, &ARXIV_PREFIXES) {
+            return parse_arxiv(rest).ok_or_else(unrecognised);
+        }
+        if let Some(rest) = strip_any_prefix(trimmed, &lower, &PUBMED_PREFIXES) {
+            let pmid = rest.trim_end_matches('/');
+            if !pmid.is_empty() && pmid.chars().all(|c| c.is_ascii_digit()) {
+                return Ok(PaperId::PubMed(pmid.to_string()));
+            }
+            return Err(unrecognised());
+        }
+        if let Some(rest) = strip_any_prefix(trimmed, &lower, &[CORPUS_PREFIX]) {
+            if !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()) {
+                return Ok(PaperId::CorpusId(rest.to_string()));
+            }
+            return Err(unrecognised());
+        }
+        if let Some(rest) = strip_any_prefix(trimmed, &lower, &S2_PREFIXES) {
+            // Paper pages look like `/paper/<title-slug>/<sha>`
+            let sha = rest.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+            return parse_s2(sha).ok_or_else(unrecognised);
+        }
+
+        parse_doi(trimmed)
+            .or_else(|| parse_arxiv(trimmed))
+            .or_else(|| parse_s2(trimmed))
+            .ok_or_else(unrecognised)
+    }
+}
+
+/// Strips the first matching prefix, comparing case-insensitively
+fn strip_any_prefix<'a>(input: &'a str, lower: &str, prefixes: &[&str]) -> Option<&'a str> {
+    prefixes
+        .iter()
+        .find(|prefix| lower.starts_with(*prefix))
+        .map(|prefix| input[prefix.len()..].trim())
+}
+
+fn parse_doi(input: &str) -> Option<PaperId> {
+    let doi = input.trim().to_lowercase();
+    let (registrant, suffix) = doi.split_once('/')?;
+    let registrant = registrant.strip_prefix("10.")?;
+    if registrant.is_empty()
+        || !registrant.chars().all(|c| c.is_ascii_digit() || c == '.')
+        || suffix.is_empty()
+    {
+        return None;
+    }
+    Some(PaperId::Doi(doi))
+}
+
+fn parse_arxiv(input: &str) -> Option<PaperId> {
+    let input = input.trim_end_matches('/');
+    let input = input.strip_suffix(".pdf").unwrap_or(input);
+    let (id, version) = split_version(input);
+    if is_new_arxiv(id) {
+        return Some(PaperId::ArXiv { id: id.to_string(), version });
+    }
+    // Old style: archive(.subject)/YYMMNNN
+    let (archive, number) = id.split_once('/')?;
+    let archive_ok = !archive.is_empty()
+        && archive
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || c == '-' || c == '.');
+    let number_ok = number.len() == 7 && number.chars().all(|c| c.is_ascii_digit());
+    if archive_ok && number_ok {
+        return Some(PaperId::ArXiv { id: id.to_string(), version });
+    }
+    None
+}
+
+/// Splits a trailing `v<N>` version off an arXiv ID
+fn split_version(input: &str) -> (&str, Option<u32>) {
+    if let Some(at) = input.rfind(['v', 'V']) {
+        if let Ok(version) = input[at + 1..].parse::<u32>() {
+            return (&input[..at], Some(version));
+        }
+    }
+    (input, None)
+}
+
+/// `YYMM.NNNN` (until 2014) or `YYMM.NNNNN`
+fn is_new_arxiv(id: &str) -> bool {
+    id.split_once('.').is_some_and(|(yymm, number)| {
+        yymm.len() == 4
+            && yymm.chars().all(|c| c.is_ascii_digit())
+            && (number.len() == 4 || number.len() == 5)
+            && number.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+fn parse_s2(input: &str) -> Option<PaperId> {
+    if input.len() == 40 && input.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(PaperId::SemanticScholar(input.to_ascii_lowercase()));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doi(doi: &str) -> PaperId {
+        PaperId::Doi(doi.to_string())
+    }
+
+    fn arxiv(id: &str, version: Option<u32>) -> PaperId {
+        PaperId::ArXiv { id: id.to_string(), version }
+    }
+
+    const SHA: &str = "204e3073870fae3d05bcbc2f6a8e263d9b72e776";
+
+    #[test]
+    fn parses() {
+        let s2 = PaperId::SemanticScholar(SHA.to_string());
+        let cases = [
+            ("10.1109/ICCV.2017.405", doi("10.1109/iccv.2017.405")),
+            ("  <10.1109/ICCV.2017.405> ", doi("10.1109/iccv.2017.405")),
+            ("doi:10.1109/ICCV.2017.405", doi("10.1109/iccv.2017.405")),
+            ("DOI: 10.1109/ICCV.2017.405", doi("10.1109/iccv.2017.405")),
+            ("urn:doi:10.1000/182", doi("10.1000/182")),
+            ("https://doi.org/10.1000/abc%2Fdef", doi("10.1000/abc/def")),
+            ("http://dx.doi.org/10.1002/(SICI)1097-4571(199806)49:8%3C693::AID-ASI3%3E3.0.CO;2-0",
+                doi("10.1002/(sici)1097-4571(199806)49:8<693::aid-asi3>3.0.co;2-0")),
+            ("doi:10.1000/abc%2Fdef", doi("10.1000/abc%2fdef")),
+            ("10.1000/abc%2Fdef", doi("10.1000/abc%2fdef")),
+            ("1706.03762", arxiv("1706.03762", None)),
+            ("arXiv:1706.03762v5", arxiv("1706.03762", Some(5))),
+            ("https://arxiv.org/abs/1706.03762v2", arxiv("1706.03762", Some(2))),
+            ("https://arxiv.org/pdf/1706.03762.pdf", arxiv("1706.03762", None)),
+            ("hep-th/9901001", arxiv("hep-th/9901001", None)),
+            ("arxiv:math.GT/0309136v1", arxiv("math.GT/0309136", Some(1))),
+            ("pmid:31452104", PaperId::PubMed("31452104".to_string())),
+            ("https://pubmed.ncbi.nlm.nih.gov/31452104/", PaperId::PubMed("31452104".to_string())),
+            ("CorpusId:13756489", PaperId::CorpusId("13756489".to_string())),
+            (SHA, s2.clone()),
+            ("s2:204E3073870FAE3D05BCBC2F6A8E263D9B72E776", s2.clone()),
+            ("https://www.semanticscholar.org/paper/Attention-is-All-you-Need-Vaswani/204e3073870fae3d05bcbc2f6a8e263d9b72e776", s2.clone()),
+            ("http://www.semanticscholar.org/paper/Attention-is-All-you-Need-Vaswani/204e3073870fae3d05bcbc2f6a8e263d9b72e776/", s2.clone()),
+            ("semanticscholar.org/paper/204e3073870fae3d05bcbc2f6a8e263d9b72e776", s2),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<PaperId>(), Ok(expected), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects() {
+        let cases = [
+            "",
+            "attention is all you need",
+            "doi:11.1000/abc",
+            "10.abc/def",
+            "10.1000/",
+            "arxiv:1706.037",
+            "pmid:12a",
+            "corpusid:",
+            "https://www.semanticscholar.org/paper/not-a-sha",
+            "https://example.org/paper",
+        ];
+        for input in cases {
+            assert!(input.parse::<PaperId>().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn s2_ids() {
+        let cases = [
+            ("10.1109/ICCV.2017.405", "DOI:10.1109/iccv.2017.405"),
+            ("1706.03762v5", "ARXIV:1706.03762"),
+            ("pmid:31452104", "PMID:31452104"),
+            ("corpusid:13756489", "CorpusId:13756489"),
+            (SHA, SHA),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<PaperId>().unwrap().to_s2_id(), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn arxiv_pdf_urls() {
+        assert_eq!(
+            arxiv("1706.03762", Some(5)).arxiv_pdf_url().as_deref(),
+            Some("https://arxiv.org/pdf/1706.03762v5.pdf")
+        );
+        assert_eq!(
+            arxiv("hep-th/9901001", None).arxiv_pdf_url().as_deref(),
+            Some("https://arxiv.org/pdf/hep-th/9901001.pdf")
+        );
+        assert_eq!(doi("10.1000/182").arxiv_pdf_url(), None);
+    }
+}
@@ -0,0 +1,716 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::PRZZIError;
+
+/// Something a key can be bound to while browsing results
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Help,
+    NextResult,
+    PreviousResult,
+    FirstResult,
+    LastResult,
+    ScrollDown,
+    ScrollUp,
+    PageDown,
+    PageUp,
+    ScrollToTop,
+    ScrollToBottom,
+    OpenInBrowser,
+    Download,
+    CopyDoi,
+    CopyUrl,
+    CopyTitle,
+    CopyBibtex,
+    CopyCitation,
+    Cite,
+    ClosePopup,
+    Cancel,
+    References,
+    Citations,
+    Authors,
+    Like,
+    Dislike,
+    Recommend,
+    Sort,
+    Filter,
+    Search,
+    Back,
+    NextMatch,
+    PreviousMatch,
+    Find,
+    FindNext,
+    FindPrevious,
+    StopFind,
+}
+
+/// When an action can be used. Find actions only apply while finding and win over the others then,
+/// so they may share keys with them
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum KeyContext {
+    Browse,
+    Find,
+}
+
+impl Action {
+    pub const ALL: [Action; 38] = [
+        Action::Quit,
+        Action::Help,
+        Action::NextResult,
+        Action::PreviousResult,
+        Action::FirstResult,
+        Action::LastResult,
+        Action::ScrollDown,
+        Action::ScrollUp,
+        Action::PageDown,
+        Action::PageUp,
+        Action::ScrollToTop,
+        Action::ScrollToBottom,
+        Action::OpenInBrowser,
+        Action::Download,
+        Action::CopyDoi,
+        Action::CopyUrl,
+        Action::CopyTitle,
+        Action::CopyBibtex,
+        Action::CopyCitation,
+        Action::Cite,
+        Action::ClosePopup,
+        Action::Cancel,
+        Action::References,
+        Action::Citations,
+        Action::Authors,
+        Action::Like,
+        Action::Dislike,
+        Action::Recommend,
+        Action::Sort,
+        Action::Filter,
+        Action::Search,
+        Action::Back,
+        Action::NextMatch,
+        Action::PreviousMatch,
+        Action::Find,
+        Action::FindNext,
+        Action::FindPrevious,
+        Action::StopFind,
+    ];
+
+    /// The name used for the action in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::NextResult => "next_result",
+            Action::PreviousResult => "previous_result",
+            Action::FirstResult => "first_result",
+            Action::LastResult => "last_result",
+            Action::ScrollDown => "scroll_down",
+            Action::ScrollUp => "scroll_up",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::ScrollToTop => "scroll_to_top",
+            Action::ScrollToBottom => "scroll_to_bottom",
+            Action::OpenInBrowser => "open_in_browser",
+            Action::Download => "download",
+            Action::CopyDoi => "copy_doi",
+            Action::CopyUrl => "copy_url",
+            Action::CopyTitle => "copy_title",
+            Action::CopyBibtex => "copy_bibtex",
+            Action::CopyCitation => "copy_citation",
+            Action::Cite => "cite",
+            Action::ClosePopup => "close_popup",
+            Action::Cancel => "cancel",
+            Action::References => "references",
+            Action::Citations => "citations",
+            Action::Authors => "authors",
+            Action::Like => "like",
+            Action::Dislike => "dislike",
+            Action::Recommend => "recommend",
+            Action::Sort => "sort",
+            Action::Filter => "filter",
+            Action::Search => "search",
+            Action::Back => "back",
+            Action::NextMatch => "next_match",
+            Action::PreviousMatch => "previous_match",
+            Action::Find => "find",
+            Action::FindNext => "find_next",
+            Action::FindPrevious => "find_previous",
+            Action::StopFind => "stop_find",
+        }
+    }
+
+    /// What the action does, as shown next to its keys
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Help => "Help",
+            Action::NextResult => "Next",
+            Action::PreviousResult => "Previous",
+            Action::FirstResult => "First",
+            Action::LastResult => "Last",
+            Action::ScrollDown => "Scroll down",
+            Action::ScrollUp => "Scroll up",
+            Action::PageDown => "Page down",
+            Action::PageUp => "Page up",
+            Action::ScrollToTop => "Start of abstract",
+            Action::ScrollToBottom => "End of abstract",
+            Action::OpenInBrowser => "Open in browser",
+            Action::Download => "Download paper",
+            Action::CopyDoi => "Copy DOI",
+            Action::CopyUrl => "Copy URL",
+            Action::CopyTitle => "Copy title",
+            Action::CopyBibtex => "Copy BibTeX",
+            Action::CopyCitation => "Copy citation",
+            Action::Cite => "Cite",
+            Action::ClosePopup => "Close Popup",
+            Action::Cancel => "Cancel",
+            Action::References => "References",
+            Action::Citations => "Citations",
+            Action::Authors => "Authors",
+            Action::Like => "Like",
+            Action::Dislike => "Dislike",
+            Action::Recommend => "More like liked papers",
+            Action::Sort => "Sort",
+            Action::Filter => "Filter",
+            Action::Search => "Search",
+            Action::Back => "Back",
+            Action::NextMatch => "Next match",
+            Action::PreviousMatch => "Previous match",
+            Action::Find => "Find in abstract",
+            Action::FindNext => "Next found",
+            Action::FindPrevious => "Previous found",
+            Action::StopFind => "Stop finding",
+        }
+    }
+
+    pub fn context(self) -> KeyContext {
+        match self {
+            Action::FindNext | Action::FindPrevious | Action::StopFind => KeyContext::Find,
+            _ => KeyContext::Browse,
+        }
+    }
+}
+
+/// A key with its modifiers. Shift is left out for characters, `G` is told apart from `g` by its case
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers;
+        if let KeyCode::Char(_) = code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    pub fn from_event(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+
+    fn is_plain_char(&self) -> bool {
+        matches!(self.code, KeyCode::Char(c) if c != ' ') && self.modifiers.is_empty()
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            KeyCode::Home => f.write_str("Home"),
+            KeyCode::End => f.write_str("End"),
+            KeyCode::Enter => f.write_str("Enter"),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::Backspace => f.write_str("Backspace"),
+            KeyCode::Tab => f.write_str("Tab"),
+            KeyCode::Delete => f.write_str("Del"),
+            KeyCode::Insert => f.write_str("Ins"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            _ => f.write_str("?"),
+        }
+    }
+}
+
+fn named_key(name: &str) -> Option<KeyCode> {
+    let code = match name.to_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "tab" => KeyCode::Tab,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "space" => KeyCode::Char(' '),
+        name => {
+            let n = name.strip_prefix('f')?.parse::<u8>().ok()?;
+            if (1..=12).contains(&n) {
+                KeyCode::F(n)
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(code)
+}
+
+/// Whether `name` is written like a function key, `f` and a number
+fn is_function_key(name: &str) -> bool {
+    name.strip_prefix(['f', 'F'])
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Parses keys written like `ctrl-r`, `G`, `pagedown`, `g g` (or `gg`) and `ctrl-x ctrl-c`
+pub fn parse_keys(text: &str) -> Result<Vec<KeyChord>, String> {
+    let mut keys = Vec::new();
+    for word in text.split_whitespace() {
+        let mut rest = word;
+        let mut modifiers = KeyModifiers::NONE;
+        loop {
+            let lower = rest.to_lowercase();
+            let (modifier, prefix) = if lower.starts_with("ctrl-") {
+                (KeyModifiers::CONTROL, "ctrl-")
+            } else if lower.starts_with("alt-") {
+                (KeyModifiers::ALT, "alt-")
+            } else if lower.starts_with("shift-") {
+                (KeyModifiers::SHIFT, "shift-")
+            } else {
+                break;
+            };
+            // `ctrl--` is Ctrl and the minus key
+            if rest.len() == prefix.len() {
+                break;
+            }
+            modifiers |= modifier;
+            rest = &rest[prefix.len()..];
+        }
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => {
+                let c = if modifiers.contains(KeyModifiers::SHIFT) { c.to_ascii_uppercase() } else { c };
+                keys.push(KeyChord::new(KeyCode::Char(c), modifiers));
+            },
+            _ => match named_key(rest) {
+                Some(code) => keys.push(KeyChord::new(code, modifiers)),
+                // Plain letters run together, like `gg`, but `f13` is not `f 1 3`
+                None if modifiers.is_empty() && !rest.is_empty() && !is_function_key(rest) => {
+                    keys.extend(rest.chars().map(|c| KeyChord::new(KeyCode::Char(c), KeyModifiers::NONE)));
+                },
+                None => return Err(format!("unknown key \"{}\"", word)),
+            },
+        }
+    }
+    if keys.is_empty() {
+        return Err("no key given".to_string());
+    }
+    Ok(keys)
+}
+
+/// How a sequence of keys is written out, e.g. `gg` or `Ctrl-x Ctrl-c`
+pub fn format_keys(keys: &[KeyChord]) -> String {
+    if keys.iter().all(KeyChord::is_plain_char) {
+        keys.iter().map(|key| key.to_string()).collect()
+    } else {
+        keys.iter().map(|key| key.to_string()).collect::<Vec<String>>().join(" ")
+    }
+}
+
+/// Bindings to start from, the config file can change any of them
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KeymapPreset {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+/// One key or several keys for an action
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The `[keys]` section of the config file
+///
+/// ```toml
+/// [keys]
+/// preset = "vim"
+///
+/// [keys.bindings]
+/// download = "D"
+/// open_in_browser = ["o", "ctrl-r"]
+/// ```
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeymapConfig {
+    pub preset: KeymapPreset,
+    /// Keys for an action, replacing the ones the preset gives it. An empty list unbinds the action
+    pub bindings: HashMap<Action, KeyList>,
+}
+
+/// What a sequence of key presses amounts to
+pub enum KeyMatch {
+    Action(Action),
+    /// The keys start a longer binding, wait for the next key
+    Pending,
+    None,
+}
+
+/// Keys bound to actions, in the order they are shown
+pub struct Keymap {
+    bindings: Vec<(Vec<KeyChord>, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::preset(KeymapPreset::Default)
+    }
+}
+
+impl Keymap {
+    pub fn preset(preset: KeymapPreset) -> Self {
+        let mut bindings: Vec<(&str, Action)> = vec![
+            ("ctrl-c", Action::Quit),
+            ("?", Action::Help),
+            ("n", Action::NextResult),
+            ("p", Action::PreviousResult),
+            ("down", Action::ScrollDown),
+            ("up", Action::ScrollUp),
+            ("pagedown", Action::PageDown),
+            ("pageup", Action::PageUp),
+            ("home", Action::ScrollToTop),
+            ("end", Action::ScrollToBottom),
+            ("ctrl-r", Action::OpenInBrowser),
+            ("ctrl-d", Action::Download),
+            ("y d", Action::CopyDoi),
+            ("y u", Action::CopyUrl),
+            ("y t", Action::CopyTitle),
+            ("y b", Action::CopyBibtex),
+            ("y c", Action::CopyCitation),
+            ("C", Action::Cite),
+            ("q", Action::ClosePopup),
+            ("esc", Action::Cancel),
+            ("r", Action::References),
+            ("c", Action::Citations),
+            ("a", Action::Authors),
+            ("+", Action::Like),
+            ("-", Action::Dislike),
+            ("m", Action::Recommend),
+            ("s", Action::Sort),
+            ("f", Action::Filter),
+            ("/", Action::Search),
+            ("backspace", Action::Back),
+            ("]", Action::NextMatch),
+            ("[", Action::PreviousMatch),
+            ("ctrl-f", Action::Find),
+            ("n", Action::FindNext),
+            ("N", Action::FindPrevious),
+            ("esc", Action::StopFind),
+        ];
+        let changes: Vec<(&str, Action)> = match preset {
+            KeymapPreset::Default => Vec::new(),
+            KeymapPreset::Vim => vec![
+                ("j", Action::NextResult),
+                ("k", Action::PreviousResult),
+                ("g g", Action::FirstResult),
+                ("G", Action::LastResult),
+                ("ctrl-e", Action::ScrollDown),
+                ("down", Action::ScrollDown),
+                ("ctrl-y", Action::ScrollUp),
+                ("up", Action::ScrollUp),
+            ],
+            KeymapPreset::Emacs => vec![
+                ("ctrl-x ctrl-c", Action::Quit),
+                ("ctrl-c", Action::Quit),
+                ("ctrl-n", Action::NextResult),
+                ("ctrl-p", Action::PreviousResult),
+                ("alt-<", Action::FirstResult),
+                ("alt->", Action::LastResult),
+                ("ctrl-v", Action::ScrollDown),
+                ("down", Action::ScrollDown),
+                ("alt-v", Action::ScrollUp),
+                ("up", Action::ScrollUp),
+                ("q", Action::ClosePopup),
+                ("ctrl-g", Action::Cancel),
+                ("esc", Action::Cancel),
+                ("ctrl-s", Action::Find),
+                ("ctrl-s", Action::FindNext),
+                ("ctrl-r", Action::FindPrevious),
+                ("ctrl-g", Action::StopFind),
+                ("esc", Action::StopFind),
+            ],
+        };
+        // A preset replaces every binding of the actions it mentions
+        bindings.retain(|(_, action)| !changes.iter().any(|(_, changed)| changed == action));
+        bindings.extend(changes);
+        let mut keymap = Keymap { bindings: Vec::new() };
+        for action in Action::ALL {
+            for (keys, _) in bindings.iter().filter(|(_, bound)| *bound == action) {
+                keymap.bindings.push((parse_keys(keys).expect("preset keys are valid"), action));
+            }
+        }
+        keymap
+    }
+
+    /// The preset from the config with the config's own bindings on top
+    pub fn from_config(config: &KeymapConfig) -> Result<Self, PRZZIError> {
+        let mut keymap = Self::preset(config.preset);
+        let mut errors: Vec<String> = Vec::new();
+        for action in Action::ALL {
+            let keys = match config.bindings.get(&action) {
+                Some(KeyList::One(keys)) => vec![keys.clone()],
+                Some(KeyList::Many(keys)) => keys.clone(),
+                None => continue,
+            };
+            let at = keymap.bindings.iter().position(|(_, bound)| *bound == action).unwrap_or(keymap.bindings.len());
+            // Bindings of an action are next to each other, so `at` still points at where they were
+            keymap.bindings.retain(|(_, bound)| *bound != action);
+            for (offset, text) in keys.iter().enumerate() {
+                match parse_keys(text) {
+                    Ok(parsed) => keymap.bindings.insert(at + offset, (parsed, action)),
+                    Err(e) => errors.push(format!("{}: {}", action.name(), e)),
+                }
+            }
+        }
+        errors.extend(keymap.conflicts());
+        if !errors.is_empty() {
+            return Err(PRZZIError {
+                msg: format!("Invalid key bindings in the config file:\n  {}", errors.join("\n  ")),
+            });
+        }
+        Ok(keymap)
+    }
+
+    /// Bindings that would shadow each other: the same keys for two actions,
+    /// or keys that are the start of a longer binding, which could then never be pressed
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for (index, (keys, action)) in self.bindings.iter().enumerate() {
+            for (other_keys, other) in &self.bindings[index + 1..] {
+                if action == other || action.context() != other.context() {
+                    continue;
+                }
+                if keys == other_keys {
+                    conflicts.push(format!(
+                        "\"{}\" is bound to both {} and {}",
+                        format_keys(keys),
+                        action.name(),
+                        other.name()
+                    ));
+                } else if other_keys.starts_with(keys) || keys.starts_with(other_keys) {
+                    let ((short, short_action), (long, long_action)) = if keys.len() < other_keys.len() {
+                        ((keys, action), (other_keys, other))
+                    } else {
+                        ((other_keys, other), (keys, action))
+                    };
+                    conflicts.push(format!(
+                        "\"{}\" ({}) keeps \"{}\" ({}) from being reached",
+                        format_keys(short),
+                        short_action.name(),
+                        format_keys(long),
+                        long_action.name()
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// The action for the keys pressed so far. While finding, find actions are looked at first
+    pub fn lookup(&self, keys: &[KeyChord], finding: bool) -> KeyMatch {
+        let mut contexts = vec![KeyContext::Browse];
+        if finding {
+            contexts.insert(0, KeyContext::Find);
+        }
+        let mut pending = false;
+        for context in contexts {
+            for (bound, action) in self.bindings.iter().filter(|(_, action)| action.context() == context) {
+                if bound.as_slice() == keys {
+                    return KeyMatch::Action(*action);
+                }
+                pending |= bound.len() > keys.len() && bound.starts_with(keys);
+            }
+        }
+        if pending {
+            KeyMatch::Pending
+        } else {
+            KeyMatch::None
+        }
+    }
+
+    /// Every key sequence bound to an action
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(keys, _)| format_keys(keys))
+            .collect()
+    }
+
+    /// The first keys bound to an action, the ones shown in hints
+    pub fn hint(&self, action: Action) -> Option<String> {
+        self.keys(action).into_iter().next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord::new(code, modifiers)
+    }
+
+    fn plain(c: char) -> KeyChord {
+        chord(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn parses() {
+        let ctrl = KeyModifiers::CONTROL;
+        let cases = [
+            ("q", vec![plain('q')]),
+            ("G", vec![plain('G')]),
+            ("shift-g", vec![plain('G')]),
+            ("ctrl-r", vec![chord(KeyCode::Char('r'), ctrl)]),
+            ("Ctrl-Alt-x", vec![chord(KeyCode::Char('x'), ctrl | KeyModifiers::ALT)]),
+            ("ctrl--", vec![chord(KeyCode::Char('-'), ctrl)]),
+            ("gg", vec![plain('g'), plain('g')]),
+            ("g g", vec![plain('g'), plain('g')]),
+            ("y c", vec![plain('y'), plain('c')]),
+            ("ctrl-x ctrl-c", vec![chord(KeyCode::Char('x'), ctrl), chord(KeyCode::Char('c'), ctrl)]),
+            ("pagedown", vec![chord(KeyCode::PageDown, KeyModifiers::NONE)]),
+            ("PgUp", vec![chord(KeyCode::PageUp, KeyModifiers::NONE)]),
+            ("shift-tab", vec![chord(KeyCode::Tab, KeyModifiers::SHIFT)]),
+            ("space", vec![plain(' ')]),
+            ("f5", vec![chord(KeyCode::F(5), KeyModifiers::NONE)]),
+            ("esc", vec![chord(KeyCode::Esc, KeyModifiers::NONE)]),
+        ];
+        for (text, expected) in cases {
+            assert!(parse_keys(text) == Ok(expected), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn rejects() {
+        for text in ["", "   ", "ctrl-nope", "f13", "alt-pagedownn"] {
+            assert!(parse_keys(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn formats() {
+        let cases = [
+            ("gg", "gg"),
+            ("y c", "yc"),
+            ("ctrl-x ctrl-c", "Ctrl-x Ctrl-c"),
+            ("space", "Space"),
+            ("g pagedown", "g PgDn"),
+            ("alt-shift-left", "Alt-Shift-←"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(format_keys(&parse_keys(text).unwrap()), expected, "{:?}", text);
+        }
+    }
+
+    fn config(preset: KeymapPreset, bindings: &[(Action, &[&str])]) -> KeymapConfig {
+        KeymapConfig {
+            preset,
+            bindings: bindings
+                .iter()
+                .map(|(action, keys)| (*action, KeyList::Many(keys.iter().map(|keys| keys.to_string()).collect())))
+                .collect(),
+        }
+    }
+
+    fn lookup(keymap: &Keymap, keys: &str, finding: bool) -> Option<Action> {
+        match keymap.lookup(&parse_keys(keys).unwrap(), finding) {
+            KeyMatch::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        for preset in [KeymapPreset::Default, KeymapPreset::Vim, KeymapPreset::Emacs] {
+            assert!(Keymap::preset(preset).conflicts().is_empty());
+        }
+    }
+
+    #[test]
+    fn conflicts() {
+        let same = config(KeymapPreset::Default, &[(Action::Download, &["q"])]);
+        let error = Keymap::from_config(&same).err().unwrap();
+        assert!(error.msg.contains("\"q\" is bound to both download and close_popup"), "{}", error.msg);
+
+        let shadowed = config(KeymapPreset::Default, &[(Action::Download, &["y"])]);
+        let error = Keymap::from_config(&shadowed).err().unwrap();
+        assert!(error.msg.contains("\"y\" (download) keeps \"yd\" (copy_doi) from being reached"), "{}", error.msg);
+
+        // Find actions only apply while finding, so they may reuse keys
+        let find = config(KeymapPreset::Default, &[(Action::FindNext, &["r"])]);
+        assert!(Keymap::from_config(&find).is_ok());
+    }
+
+    #[test]
+    fn bindings_override_the_preset() {
+        let custom = config(
+            KeymapPreset::Vim,
+            &[(Action::NextResult, &["ctrl-j", "J"]), (Action::Sort, &[]), (Action::Download, &["D"])],
+        );
+        let keymap = Keymap::from_config(&custom).unwrap();
+        assert_eq!(lookup(&keymap, "ctrl-j", false), Some(Action::NextResult));
+        assert_eq!(lookup(&keymap, "J", false), Some(Action::NextResult));
+        assert_eq!(lookup(&keymap, "j", false), None);
+        assert_eq!(lookup(&keymap, "s", false), None);
+        assert_eq!(lookup(&keymap, "D", false), Some(Action::Download));
+        assert_eq!(lookup(&keymap, "ctrl-d", false), None);
+        // The rest of the preset is kept
+        assert_eq!(lookup(&keymap, "k", false), Some(Action::PreviousResult));
+        assert_eq!(lookup(&keymap, "g g", false), Some(Action::FirstResult));
+        assert!(matches!(keymap.lookup(&parse_keys("g").unwrap(), false), KeyMatch::Pending));
+        assert_eq!(keymap.keys(Action::NextResult), ["Ctrl-j", "J"]);
+    }
+
+    #[test]
+    fn find_keys_win_while_finding() {
+        let keymap = Keymap::default();
+        assert_eq!(lookup(&keymap, "n", false), Some(Action::NextResult));
+        assert_eq!(lookup(&keymap, "n", true), Some(Action::FindNext));
+        assert_eq!(lookup(&keymap, "esc", true), Some(Action::StopFind));
+        assert_eq!(lookup(&keymap, "r", true), Some(Action::References));
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use tui::style::{Color, Modifier, Style};
+
+use crate::PRZZIError;
+
+/// The 16 ANSI colors with the RGB values terminals commonly give them,
+/// true colors are drawn as the closest of these when the terminal cant show them
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (128, 0, 0)),
+    (Color::Green, (0, 128, 0)),
+    (Color::Yellow, (128, 128, 0)),
+    (Color::Blue, (0, 0, 128)),
+    (Color::Magenta, (128, 0, 128)),
+    (Color::Cyan, (0, 128, 128)),
+    (Color::Gray, (192, 192, 192)),
+    (Color::DarkGray, (128, 128, 128)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (0, 0, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// `TERM` of terminals that always show 24-bit colors
+const TRUECOLOR_TERMS: [&str; 6] = ["xterm-kitty", "xterm-ghostty", "alacritty", "wezterm", "foot", "contour"];
+
+/// `TERM_PROGRAM` of terminals that always show 24-bit colors
+const TRUECOLOR_PROGRAMS: [&str; 5] = ["iTerm.app", "WezTerm", "vscode", "ghostty", "Hyper"];
+
+/// Colors of a theme that the config file can set
+const COLOR_NAMES: [&str; 14] = [
+    "background", "border", "text", "title", "accent", "highlight", "found", "found_current", "found_text",
+    "popup_background", "popup_text", "info", "success", "error",
+];
+
+/// The built-in themes
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+/// The `[theme]` section of the config file
+///
+/// ```toml
+/// [theme]
+/// name = "light"
+///
+/// [theme.colors]
+/// title = "#aa0000"
+/// accent = "green"
+/// ```
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub name: ThemeName,
+    /// Colors replacing the ones of the built-in theme, see `COLOR_NAMES`
+    pub colors: HashMap<String, String>,
+    /// Whether the terminal can show 24-bit colors, worked out from `COLORTERM` and `TERM` when not set
+    pub truecolor: Option<bool>,
+}
+
+/// Colors of the TUI
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub background: Color,
+    pub border: Color,
+    pub text: Color,
+    /// Box titles
+    pub title: Color,
+    /// Labels of prompts and what a key does in hints
+    pub accent: Color,
+    /// Words of the search query
+    pub highlight: Color,
+    /// Background of text found with Ctrl-f, and of the occurrence scrolled to
+    pub found: Color,
+    pub found_current: Color,
+    /// Text on top of `found` and `found_current`
+    pub found_text: Color,
+    pub popup_background: Color,
+    pub popup_text: Color,
+    pub info: Color,
+    pub success: Color,
+    pub error: Color,
+    /// No colors at all, emphasis comes from bold and reversed text only
+    pub plain: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::builtin(ThemeName::Dark)
+    }
+}
+
+impl Theme {
+    pub fn builtin(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Theme {
+                background: Color::Rgb(40, 40, 40),
+                border: Color::LightBlue,
+                text: Color::Rgb(213, 196, 161),
+                title: Color::Red,
+                accent: Color::Green,
+                highlight: Color::Yellow,
+                found: Color::Yellow,
+                found_current: Color::LightRed,
+                found_text: Color::Black,
+                popup_background: Color::White,
+                popup_text: Color::Black,
+                info: Color::Yellow,
+                success: Color::Green,
+                error: Color::Red,
+                plain: false,
+            },
+            ThemeName::Light => Theme {
+                background: Color::Rgb(250, 248, 240),
+                border: Color::Rgb(40, 80, 160),
+                text: Color::Rgb(40, 40, 40),
+                title: Color::Rgb(170, 20, 20),
+                accent: Color::Rgb(0, 110, 40),
+                highlight: Color::Rgb(170, 90, 0),
+                found: Color::Rgb(255, 220, 90),
+                found_current: Color::Rgb(255, 150, 120),
+                found_text: Color::Black,
+                popup_background: Color::Rgb(225, 225, 225),
+                popup_text: Color::Black,
+                info: Color::Rgb(150, 100, 0),
+                success: Color::Rgb(0, 120, 40),
+                error: Color::Rgb(180, 0, 0),
+                plain: false,
+            },
+            // Only the basic ANSI colors, every terminal can show them
+            ThemeName::HighContrast => Theme {
+                background: Color::Black,
+                border: Color::White,
+                text: Color::White,
+                title: Color::LightCyan,
+                accent: Color::LightGreen,
+                highlight: Color::LightYellow,
+                found: Color::LightYellow,
+                found_current: Color::LightMagenta,
+                found_text: Color::Black,
+                popup_background: Color::Black,
+                popup_text: Color::White,
+                info: Color::LightYellow,
+                success: Color::LightGreen,
+                error: Color::LightRed,
+                plain: false,
+            },
+        }
+    }
+
+    /// The terminal's own colors, for `NO_COLOR`
+    pub fn plain() -> Self {
+        Theme {
+            background: Color::Reset,
+            border: Color::Reset,
+            text: Color::Reset,
+            title: Color::Reset,
+            accent: Color::Reset,
+            highlight: Color::Reset,
+            found: Color::Reset,
+            found_current: Color::Reset,
+            found_text: Color::Reset,
+            popup_background: Color::Reset,
+            popup_text: Color::Reset,
+            info: Color::Reset,
+            success: Color::Reset,
+            error: Color::Reset,
+            plain: true,
+        }
+    }
+
+    /// The theme from the config, made to fit what the terminal can show
+    pub fn from_config(config: &ThemeConfig) -> Result<Self, PRZZIError> {
+        // https://no-color.org
+        if std::env::var("NO_COLOR").is_ok_and(|value| !value.is_empty()) {
+            return Ok(Self::plain());
+        }
+        let mut theme = Self::builtin(config.name);
+        let mut errors: Vec<String> = Vec::new();
+        let mut names: Vec<&String> = config.colors.keys().collect();
+        names.sort();
+        for name in names {
+            let value = &config.colors[name];
+            let color = match parse_color(value) {
+                Some(color) => color,
+                None => {
+                    errors.push(format!("{}: unknown color \"{}\"", name, value));
+                    continue;
+                }
+            };
+            match theme.color_mut(name) {
+                Some(slot) => *slot = color,
+                None => errors.push(format!(
+                    "unknown theme color \"{}\", expected one of {}",
+                    name,
+                    COLOR_NAMES.join(", ")
+                )),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(PRZZIError {
+                msg: format!("Invalid theme in the config file:\n  {}", errors.join("\n  ")),
+            });
+        }
+        if !config.truecolor.unwrap_or_else(supports_truecolor) {
+            theme = theme.to_ansi();
+        }
+        Ok(theme)
+    }
+
+    fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        let color = match name {
+            "background" => &mut self.background,
+            "border" => &mut self.border,
+            "text" => &mut self.text,
+            "title" => &mut self.title,
+            "accent" => &mut self.accent,
+            "highlight" => &mut self.highlight,
+            "found" => &mut self.found,
+            "found_current" => &mut self.found_current,
+            "found_text" => &mut self.found_text,
+            "popup_background" => &mut self.popup_background,
+            "popup_text" => &mut self.popup_text,
+            "info" => &mut self.info,
+            "success" => &mut self.success,
+            "error" => &mut self.error,
+            _ => return None,
+        };
+        Some(color)
+    }
+
+    /// The same theme with every true color swapped for the closest ANSI color
+    pub fn to_ansi(mut self) -> Self {
+        for name in COLOR_NAMES {
+            if let Some(color) = self.color_mut(name) {
+                *color = nearest_ansi(*color);
+            }
+        }
+        self
+    }
+
+    /// The whole screen, its borders take the `border` color
+    pub fn background(&self) -> Style {
+        Style::default().bg(self.background).fg(self.border)
+    }
+
+    pub fn text(&self) -> Style {
+        Style::default().fg(self.text)
+    }
+
+    pub fn title(&self) -> Style {
+        Style::default().fg(self.title)
+    }
+
+    pub fn accent(&self) -> Style {
+        Style::default().fg(self.accent)
+    }
+
+    pub fn highlight(&self) -> Style {
+        Style::default().fg(self.highlight).add_modifier(Modifier::BOLD)
+    }
+
+    pub fn found(&self) -> Style {
+        let style = Style::default().fg(self.found_text).bg(self.found);
+        if self.plain {
+            style.add_modifier(Modifier::REVERSED)
+        } else {
+            style
+        }
+    }
+
+    pub fn found_current(&self) -> Style {
+        let style = Style::default().fg(self.found_text).bg(self.found_current);
+        if self.plain {
+            style.add_modifier(Modifier::REVERSED | Modifier::BOLD | Modifier::UNDERLINED)
+        } else {
+            style
+        }
+    }
+
+    pub fn popup(&self) -> Style {
+        Style::default().bg(self.popup_background).fg(self.popup_text)
+    }
+
+    /// Title color of a popup by its type: `Info`, `Success` or `Error!`
+    pub fn popup_title(&self, popup_type: &str) -> Style {
+        let color = match popup_type {
+            "Error!" => self.error,
+            "Success" => self.success,
+            _ => self.info,
+        };
+        Style::default().fg(color)
+    }
+}
+
+/// Whether the terminal says it can show 24-bit colors
+pub fn supports_truecolor() -> bool {
+    let var = |name| std::env::var(name).ok();
+    truecolor_from(var("COLORTERM").as_deref(), var("TERM").as_deref(), var("TERM_PROGRAM").as_deref())
+}
+
+/// Whether `COLORTERM`, `TERM` or `TERM_PROGRAM` name a terminal with 24-bit colors.
+/// `COLORTERM` is often not passed on over ssh or sudo, while `TERM` is
+fn truecolor_from(colorterm: Option<&str>, term: Option<&str>, term_program: Option<&str>) -> bool {
+    let colorterm = colorterm.unwrap_or("").to_lowercase();
+    let term = term.unwrap_or("").to_lowercase();
+    colorterm == "truecolor"
+        || colorterm == "24bit"
+        || term.ends_with("-direct")
+        || term.contains("truecolor")
+        || term.contains("24bit")
+        || TRUECOLOR_TERMS.iter().any(|name| term.starts_with(name))
+        || term_program.is_some_and(|program| TRUECOLOR_PROGRAMS.contains(&program))
+}
+
+fn nearest_ansi(color: Color) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r as i32, g as i32, b as i32),
+        _ => return color,
+    };
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, (ar, ag, ab))| (r - *ar as i32).pow(2) + (g - *ag as i32).pow(2) + (b - *ab as i32).pow(2))
+        .map(|(ansi, _)| *ansi)
+        .unwrap_or(color)
+}
+
+/// Parses `#rrggbb`, a color name like `lightred` or `light-red`, `reset`, or a 0-255 palette index
+pub fn parse_color(text: &str) -> Option<Color> {
+    let text = text.trim();
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(index) = text.parse::<u8>() {
+        return Some(Color::Indexed(index));
+    }
+    let name = text.to_lowercase().replace(['-', '_', ' '], "").replace("grey", "gray");
+    let color = match name.as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" => Color::Gray,
+        "darkgray" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    };
+    Some(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        let cases = [
+            ("#aa0000", Some(Color::Rgb(170, 0, 0))),
+            ("#FaF8f0", Some(Color::Rgb(250, 248, 240))),
+            (" #000000 ", Some(Color::Rgb(0, 0, 0))),
+            ("red", Some(Color::Red)),
+            ("LightRed", Some(Color::LightRed)),
+            ("light-red", Some(Color::LightRed)),
+            ("light_cyan", Some(Color::LightCyan)),
+            ("dark grey", Some(Color::DarkGray)),
+            ("reset", Some(Color::Reset)),
+            ("default", Some(Color::Reset)),
+            ("0", Some(Color::Indexed(0))),
+            ("208", Some(Color::Indexed(208))),
+            ("#aa00", None),
+            ("#aa00000", None),
+            ("#gg0000", None),
+            ("#ffé00", None),
+            ("aa0000", None),
+            ("256", None),
+            ("-1", None),
+            ("purple", None),
+            ("", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_color(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn falls_back_to_ansi() {
+        let cases = [
+            (Color::Rgb(40, 40, 40), Color::Black),
+            (Color::Rgb(213, 196, 161), Color::Gray),
+            (Color::Rgb(250, 248, 240), Color::White),
+            (Color::Rgb(170, 20, 20), Color::Red),
+            (Color::Rgb(240, 10, 10), Color::LightRed),
+            (Color::Rgb(0, 110, 40), Color::Green),
+            (Color::Rgb(20, 20, 150), Color::Blue),
+            (Color::Rgb(120, 120, 120), Color::DarkGray),
+            // Colors that are not true colors stay as they are
+            (Color::LightBlue, Color::LightBlue),
+            (Color::Indexed(208), Color::Indexed(208)),
+            (Color::Reset, Color::Reset),
+        ];
+        for (color, expected) in cases {
+            assert_eq!(nearest_ansi(color), expected, "{:?}", color);
+        }
+
+        let theme = Theme::builtin(ThemeName::Light).to_ansi();
+        for name in COLOR_NAMES {
+            let mut theme = theme;
+            assert!(!matches!(theme.color_mut(name), Some(Color::Rgb(..))), "{}", name);
+        }
+    }
+
+    #[test]
+    fn detects_truecolor() {
+        let cases = [
+            (Some("truecolor"), None, None, true),
+            (Some("24bit"), Some("xterm-256color"), None, true),
+            (Some("TrueColor"), None, None, true),
+            (None, Some("xterm-direct"), None, true),
+            (None, Some("tmux-direct"), None, true),
+            (None, Some("xterm-kitty"), None, true),
+            (None, Some("alacritty"), None, true),
+            (None, Some("xterm-256color"), Some("iTerm.app"), true),
+            (None, Some("xterm-256color"), Some("vscode"), true),
+            (None, Some("xterm-256color"), None, false),
+            (None, Some("screen-256color"), Some("tmux"), false),
+            (Some("yes"), Some("linux"), None, false),
+            (None, Some("xterm-256color"), Some("Apple_Terminal"), false),
+            (None, None, None, false),
+        ];
+        for (colorterm, term, program, expected) in cases {
+            assert_eq!(truecolor_from(colorterm, term, program), expected, "{:?} {:?} {:?}", colorterm, term, program);
+        }
+    }
+}
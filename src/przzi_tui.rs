@@ -1,32 +1,107 @@
 use crossterm::{
+    cursor::Show,
     terminal::{
-        EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
+        enable_raw_mode, disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
+    },
+    event::{
+        EnableMouseCapture, DisableMouseCapture, Event, poll, read, KeyEvent, KeyCode, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
     },
-    event::{EnableMouseCapture, DisableMouseCapture, Event::{Key}, poll, read, KeyEvent, KeyCode, KeyModifiers},
     execute,
     ExecutableCommand
 };
 use tui::{
     backend::{Backend, CrosstermBackend},
+    buffer::Buffer,
     layout::{Constraint, Direction, Layout, Alignment, Rect}, 
-    widgets::{Block, Borders, Paragraph, Wrap, BorderType, Clear},
+    widgets::{Block, Borders, Paragraph, Wrap, BorderType, Clear, List, ListItem, ListState, Widget},
     Terminal,
     Frame,
     text::{Span, Spans},
-    style::{Style, Color, Modifier}
+    style::{Style, Modifier}
 };
-use url::Url;
 use std::io::stdout;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use crate::PRZZIError;
 use crate::PRZZIResult;
-use crate::PRZZI;
+use crate::PRZZIAuthorProfile;
+use crate::{Query, SearchFilters};
+use crate::przzi_api::PRZZIClient;
+use crate::przzi_cite::{self, CitationStyle};
+use crate::przzi_clipboard::{self, Copied};
+use crate::przzi_download::{self, DownloadError};
+use crate::przzi_highlight;
+use crate::przzi_history::{self, HistoryEntry};
+use crate::przzi_id::PaperId;
+use crate::przzi_keymap::{Action, KeyChord, KeyMatch, Keymap};
+use crate::przzi_theme::Theme;
+use crate::przzi_watch::{self, WatchRun};
+use crate::przzi_worker::{Failed, Workers};
+
+
+/// Whether the terminal is in raw mode on the alternate screen, and so has to be restored
+static TERMINAL_TAKEN: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK: Once = Once::new();
+
+/// Leaves raw mode, the alternate screen and mouse capture, if the UI took the terminal.
+/// Errors are ignored, this runs while unwinding too
+fn restore_terminal() {
+    if TERMINAL_TAKEN.swap(false, Ordering::SeqCst) {
+        let _ = disable_raw_mode();
+        let _ = execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture, Show);
+    }
+}
+
+/// Restores the terminal before the panic message is printed, it would be lost on the alternate screen otherwise
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            // A background task panicking only ends that task, the UI keeps running on the main thread.
+            // The task reports the panic itself, printing it would land on the UI
+            if std::thread::current().name() == Some("main") {
+                restore_terminal();
+                default_hook(info);
+            } else if !TERMINAL_TAKEN.load(Ordering::SeqCst) {
+                default_hook(info);
+            }
+        }));
+    });
+}
+
+/// Puts the terminal in raw mode on the alternate screen, and restores it when dropped,
+/// whether the UI quits, returns an error or panics
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new(mouse: bool) -> Result<Self, PRZZIError> {
+        install_panic_hook();
+        // Set before anything is changed so a failure halfway is undone as well
+        TERMINAL_TAKEN.store(true, Ordering::SeqCst);
+        let guard = TerminalGuard;
+        enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen)?;
+        if mouse {
+            execute!(stdout(), EnableMouseCapture)?;
+        }
+        Ok(guard)
+    }
+}
 
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
 
 struct Popup {
     show_popup: bool,
     popup_msg: String,
     popup_type: String, //Info by default
-    popup_color: Color // yellow for Info, Green for Success and Red for Error
 }
 
 impl Popup {
@@ -35,7 +110,6 @@ impl Popup {
             show_popup: false,
             popup_msg: "".to_string(),
             popup_type: "Info".to_string(),
-            popup_color: Color::Yellow,
         }
     }
 
@@ -64,14 +138,15 @@ impl Popup {
             .split(popup_layout[1])[1]
     }
     
-    fn get_para(&self) -> Paragraph{
+    /// The title takes the theme's info, success or error color
+    fn get_para(&self, theme: &Theme) -> Paragraph<'_>{
         Paragraph::new(self.popup_msg.as_ref())
             .alignment(Alignment::Center)
             .block(
                 Block::default()
-                .title(Span::styled(self.popup_type.clone(), Style::default().fg(self.popup_color)))
+                .title(Span::styled(self.popup_type.clone(), theme.popup_title(&self.popup_type)))
                 .borders(Borders::ALL)
-                .style(Style::default().bg(Color::White).fg(Color::Black))
+                .style(theme.popup())
             )
             .wrap(Wrap {trim: true})
     }
@@ -81,78 +156,1601 @@ impl Popup {
     }
 
     fn open(&mut self, msg: String, popup_type: String){
-        if popup_type == "Error!"{
-            self.popup_color = Color::Red;
-        }
-        else if popup_type == "Info" {
-            self.popup_color = Color::Yellow;
-        }
-        else if popup_type == "Success" {
-            self.popup_color = Color::Green;
-        }
         self.popup_type = popup_type;
         self.popup_msg = msg;
         self.show_popup = true;
     }
 }
 
+// How many references / citations are fetched when following a paper
+const LINK_LIMIT: usize = 100;
+// How many papers are fetched for an author
+const AUTHOR_PAPER_LIMIT: usize = 500;
+// How many recommendations are fetched from the liked / disliked papers
+const RECOMMENDATION_LIMIT: usize = 20;
+// How many people with a matching name are offered for `--author`
+const AUTHOR_CANDIDATES: usize = 20;
+// How many papers a search from the `/` prompt fetches
+const SEARCH_LIMIT: usize = 10;
+// How many lines a turn of the mouse wheel scrolls
+const MOUSE_SCROLL_LINES: u16 = 3;
+/// How long to wait for input before drawing again, keeps spinners turning while nothing happens
+const TICK: Duration = Duration::from_millis(100);
+/// How often a download reports its progress
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// Characters of a paper's title shown next to its running download
+const DOWNLOAD_TITLE_WIDTH: usize = 30;
+/// Below this size only a message asking for a bigger terminal is drawn
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 12;
+/// From this width the result list goes next to the paper, taking `LIST_PERCENT` of the width
+const SIDE_BY_SIDE_WIDTH: u16 = 110;
+const LIST_PERCENT: u16 = 35;
+/// From this height the result list goes above the paper when it is not next to it
+const STACKED_LIST_HEIGHT: u16 = 30;
+/// Rows of the result list above the paper, borders included
+const STACKED_LIST_ROWS: u16 = 7;
+/// Rows of an author's profile box
+const AUTHOR_ROWS: u16 = 4;
+
+/// A result list left behind when following references or citations, restored with Backspace
+struct ResultFrame {
+    label: String,
+    loaded: Vec<PRZZIResult>,
+    results: Vec<PRZZIResult>,
+    result_index: usize,
+    scroll: u16,
+    author: Option<AuthorView>,
+    sort: ResultSort,
+    filter: String,
+    query: Option<String>,
+}
+
+/// Order of the result list, cycled with `s`
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ResultSort {
+    /// The order the API returned
+    Relevance,
+    Year,
+    Citations,
+    Title,
+}
+
+impl ResultSort {
+    fn name(self) -> &'static str {
+        match self {
+            ResultSort::Relevance => "relevance",
+            ResultSort::Year => "year",
+            ResultSort::Citations => "citations",
+            ResultSort::Title => "title",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            ResultSort::Relevance => ResultSort::Year,
+            ResultSort::Year => ResultSort::Citations,
+            ResultSort::Citations => ResultSort::Title,
+            ResultSort::Title => ResultSort::Relevance,
+        }
+    }
+}
+
+/// Whether the letters of `needle` appear in `haystack` in order, not necessarily next to each other
+fn fuzzy_match(haystack: &str, needle: &str) -> bool {
+    let mut letters = haystack.chars().flat_map(char::to_lowercase);
+    needle.chars().all(|c| letters.any(|letter| letter == c))
+}
+
+/// Every word of the filter has to be in the title, an author or the abstract.
+/// Titles and author names also match fuzzily so `gan` finds "Generative Adversarial Networks"
+fn matches_filter(result: &PRZZIResult, filter: &str) -> bool {
+    let title = result.title.to_lowercase();
+    let authors = result.authors.iter().map(|author| author.name.to_lowercase()).collect::<Vec<String>>();
+    let abs = result.abs.to_lowercase();
+    filter.to_lowercase().split_whitespace().all(|word| {
+        title.contains(word)
+            || abs.contains(word)
+            || authors.iter().any(|author| author.contains(word))
+            || fuzzy_match(&title, word)
+            || authors.iter().any(|author| fuzzy_match(author, word))
+    })
+}
+
+/// Text looked for in the abstract with Ctrl-f
+struct Find {
+    input: String,
+    /// Whether the text is still being typed in, `n` / `N` move between matches once it is not
+    editing: bool,
+    /// The occurrence scrolled to
+    current: usize,
+}
+
+/// The author whose papers are being listed
+struct AuthorView {
+    profile: PRZZIAuthorProfile,
+}
+
+/// A list of authors to choose from, drawn over the results
+struct AuthorPicker {
+    title: String,
+    /// label and Semantic Scholar author ID
+    authors: Vec<(String, Option<String>)>,
+    state: ListState,
+    /// First author in view, kept the way the list widget scrolls so clicks land on the right row
+    offset: usize,
+}
+
+impl AuthorPicker {
+    fn new(title: String, authors: Vec<(String, Option<String>)>) -> Self {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Self {
+            title,
+            authors,
+            state,
+            offset: 0,
+        }
+    }
+
+    /// Scrolls just enough to keep the selected author in a list `height` rows high
+    fn scroll_into_view(&mut self, height: usize) {
+        self.offset = keep_in_view(self.offset, self.selected(), height);
+    }
+
+    fn selected(&self) -> usize {
+        self.state.selected().unwrap_or(0)
+    }
+
+    fn next(&mut self) {
+        if self.selected() + 1 < self.authors.len() {
+            self.state.select(Some(self.selected() + 1));
+        }
+    }
+
+    fn previous(&mut self) {
+        if self.selected() > 0 {
+            self.state.select(Some(self.selected() - 1));
+        }
+    }
+}
+
+/// The `/` search prompt, drawn in place of the footer
+struct SearchPrompt {
+    input: String,
+    filters: SearchFilters,
+    num_results: usize,
+    /// Past searches, newest first and without repeats, recalled with Up / Down
+    history: Vec<HistoryEntry>,
+    /// Which of `history` is being shown, `None` while typing a new search
+    recalled: Option<usize>,
+}
+
+impl SearchPrompt {
+    fn new() -> Self {
+        let mut history: Vec<HistoryEntry> = Vec::new();
+        for entry in przzi_history::load().unwrap_or_default().into_iter().rev() {
+            if !history.iter().any(|seen| seen.query == entry.query && seen.filters == entry.filters) {
+                history.push(entry);
+            }
+        }
+        Self {
+            input: String::new(),
+            filters: SearchFilters::default(),
+            num_results: SEARCH_LIMIT,
+            history,
+            recalled: None,
+        }
+    }
+
+    fn recall(&mut self, recalled: Option<usize>) {
+        self.recalled = recalled;
+        match recalled.and_then(|index| self.history.get(index)) {
+            Some(entry) => {
+                self.input = entry.query.clone();
+                self.filters = entry.filters.clone();
+                self.num_results = entry.num_results;
+            },
+            None => {
+                self.input.clear();
+                self.filters = SearchFilters::default();
+                self.num_results = SEARCH_LIMIT;
+            }
+        }
+    }
+
+    fn older(&mut self) {
+        let next = self.recalled.map_or(0, |index| index + 1);
+        if next < self.history.len() {
+            self.recall(Some(next));
+        }
+    }
+
+    fn newer(&mut self) {
+        match self.recalled {
+            Some(0) | None => self.recall(None),
+            Some(index) => self.recall(Some(index - 1)),
+        }
+    }
+}
+
+/// A line of key hints in the footer
+struct FooterLine {
+    text: Spans<'static>,
+    /// Start column, width and action of every clickable hint, from the start of the line
+    buttons: Vec<(u16, u16, Action)>,
+}
+
+#[derive(Clone, Copy)]
+enum Link {
+    References,
+    Citations,
+}
+
+/// A search to run, with what the history needs to know about it
+struct SearchRequest {
+    query: String,
+    num_results: usize,
+    filters: SearchFilters,
+}
+
+/// What the UI fetches as soon as it is up, when it starts without papers
+enum StartFetch {
+    Search(SearchRequest),
+    Paper(PaperId),
+    /// People whose name matches, to pick the author from
+    Authors(String),
+    /// Papers recommended from seed papers, and ones they should not be like
+    Recommendations { seeds: Vec<PaperId>, negative: Vec<PaperId>, limit: usize },
+    /// Runs the saved watches, or only the named one
+    Watches(Option<String>),
+}
+
+/// A result list fetched in the background
+enum FetchedList {
+    /// `replace` shows the results in place of the current list, for the search the UI started with
+    Search { request: SearchRequest, replace: bool, results: Result<Vec<PRZZIResult>, PRZZIError> },
+    Author(Result<(PRZZIAuthorProfile, Vec<PRZZIResult>), PRZZIError>),
+    Linked { link: Link, label: String, results: Result<Vec<PRZZIResult>, PRZZIError> },
+    Recommendations { label: String, results: Result<Vec<PRZZIResult>, PRZZIError> },
+    Paper(Result<Box<PRZZIResult>, PRZZIError>),
+    AuthorCandidates { name: String, results: Result<Vec<PRZZIAuthorProfile>, PRZZIError> },
+    Seeded(Result<Seeded, PRZZIError>),
+    Watches(Result<WatchRun, PRZZIError>),
+}
+
+/// Papers recommended from seed papers
+struct Seeded {
+    /// Semantic Scholar IDs of the seeds
+    liked: Vec<String>,
+    /// Semantic Scholar IDs of the papers the recommendations should not be like
+    disliked: Vec<String>,
+    results: Vec<PRZZIResult>,
+}
+
+/// Papers recommended from seeds given by any kind of ID
+fn recommend_from_seeds(client: &PRZZIClient, seeds: &[PaperId], negative: &[PaperId], limit: usize) -> Result<Seeded, PRZZIError> {
+    let mut positive = seeds.iter().map(|id| client.resolve_paper_id(id)).collect::<Result<Vec<_>, _>>()?;
+    let mut negative = negative.iter().map(|id| client.resolve_paper_id(id)).collect::<Result<Vec<_>, _>>()?;
+    // Different IDs can point at the same paper
+    positive.sort();
+    positive.dedup();
+    negative.sort();
+    negative.dedup();
+    let results = client.recommendations(&positive, &negative, limit)?;
+    Ok(Seeded { liked: positive, disliked: negative, results })
+}
+
+/// What background work sends back to the UI
+enum Fetched {
+    List(FetchedList),
+    DownloadProgress(u64, Option<u64>),
+    Downloaded(Result<PathBuf, DownloadError>),
+}
+
+/// How far text `rows` lines long can scroll in a box showing `visible` of them, so its last line stays at the bottom
+fn scroll_limit(rows: usize, visible: u16) -> u16 {
+    u16::try_from(rows).unwrap_or(u16::MAX).saturating_sub(visible)
+}
+
+/// First row to show of a list `height` rows high so the selected row is in view, scrolling as little as possible
+fn keep_in_view(offset: usize, selected: usize, height: usize) -> usize {
+    if selected < offset {
+        selected
+    } else if height > 0 && selected >= offset + height {
+        selected + 1 - height
+    } else {
+        offset
+    }
+}
+
+/// Scrollbar thumb drawn over the right border of a box whose text is taller than the box
+struct ScrollThumb {
+    scroll: u16,
+    max_scroll: u16,
+    style: Style,
+}
+
+impl Widget for ScrollThumb {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let track = area.height.saturating_sub(2);
+        if self.max_scroll == 0 || track == 0 || area.width == 0 {
+            return;
+        }
+        // The thumb is as much of the track as the box shows of the text
+        let rows = (track + self.max_scroll) as u32;
+        let size = ((track as u32 * track as u32) / rows).max(1) as u16;
+        let top = ((track - size) as u32 * self.scroll.min(self.max_scroll) as u32 / self.max_scroll as u32) as u16;
+        let x = area.x + area.width - 1;
+        for y in area.y + 1 + top..area.y + 1 + top + size {
+            buf.get_mut(x, y).set_symbol("█").set_style(self.style);
+        }
+    }
+}
+
+/// Where each part of the screen was laid out for the terminal size
+struct ScreenLayout {
+    author: Option<Rect>,
+    list: Option<Rect>,
+    title: Rect,
+    abstract_text: Rect,
+    footer: Rect,
+}
+
+/// Cuts text down to `width` characters, ending in `…` when it was longer
+fn shorten(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        format!("{}…", text.chars().take(width.saturating_sub(1)).collect::<String>())
+    }
+}
+
+impl Link {
+    fn name(self) -> &'static str {
+        match self {
+            Link::References => "References",
+            Link::Citations => "Citations",
+        }
+    }
+}
+
 pub struct PRZZITUI {
+    /// Every paper of the current list in the order the API returned them
+    loaded: Vec<PRZZIResult>,
+    /// The papers shown, `loaded` after sorting and filtering
     results: Vec<PRZZIResult>,
     result_index: usize,
     scroll: u16,
     popup: Popup,
+    label: String,
+    back_stack: Vec<ResultFrame>,
+    client: PRZZIClient,
+    author: Option<AuthorView>,
+    picker: Option<AuthorPicker>,
+    prompt: Option<SearchPrompt>,
+    sort: ResultSort,
+    /// Quick filter over the loaded papers, no request is made for it
+    filter: String,
+    /// Whether the filter is being typed in
+    editing_filter: bool,
+    /// Search query of the current list, its words are highlighted
+    query: Option<String>,
+    /// The abstract as last drawn, broken into lines to fit the screen
+    abstract_lines: Vec<String>,
+    /// Which match of the query in the abstract was jumped to with `]` / `[`
+    current_match: Option<usize>,
+    find: Option<Find>,
+    /// Authors matching `--author`, chosen from before any papers are shown
+    author_candidates: Vec<PRZZIAuthorProfile>,
+    /// Paper IDs used as positive / negative seeds for recommendations
+    liked: Vec<String>,
+    disliked: Vec<String>,
+    /// Paper IDs found by `watch run` that had not been seen before
+    new_papers: Vec<String>,
+    keymap: Keymap,
+    theme: Theme,
+    /// Whether the `?` overlay listing every key is open, and how far it is scrolled
+    show_help: bool,
+    help_scroll: u16,
+    /// Where the help overlay was last drawn, to know how far it scrolls
+    help_area: Rect,
+    /// Whether the citation of the current paper is shown, and in which style
+    cite_preview: bool,
+    citation_style: CitationStyle,
+    /// Whether the mouse is captured, when it is not the terminal selects text as usual
+    mouse: bool,
+    /// Where things were last drawn, to tell what was clicked or scrolled
+    title_area: Rect,
+    abstract_area: Rect,
+    list_area: Rect,
+    picker_area: Rect,
+    footer_buttons: Vec<(Rect, Action)>,
+    /// First paper shown in the result list
+    list_offset: usize,
+    /// Keys pressed so far of a binding that takes several, like `gg`
+    pending_keys: Vec<KeyChord>,
+    /// Requests and downloads running in the background
+    workers: Workers<Fetched>,
+    /// The task fetching the next result list, starting another fetch cancels it
+    list_task: Option<usize>,
+    /// Search to run once the UI is up
+    start_fetch: Option<StartFetch>,
+}
+
+impl Default for PRZZITUI {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PRZZITUI {
     pub fn new() -> Self {
         Self {
+            loaded: Vec::new(),
             results: Vec::new(),
             result_index: 0,
-            scroll: 0 as u16,
-            popup: Popup::new()
+            scroll: 0,
+            popup: Popup::new(),
+            label: "Results".to_string(),
+            back_stack: Vec::new(),
+            client: PRZZIClient::new(),
+            author: None,
+            picker: None,
+            prompt: None,
+            sort: ResultSort::Relevance,
+            filter: String::new(),
+            editing_filter: false,
+            query: None,
+            abstract_lines: Vec::new(),
+            current_match: None,
+            find: None,
+            author_candidates: Vec::new(),
+            liked: Vec::new(),
+            disliked: Vec::new(),
+            new_papers: Vec::new(),
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            show_help: false,
+            help_scroll: 0,
+            help_area: Rect::default(),
+            cite_preview: false,
+            citation_style: CitationStyle::default(),
+            mouse: true,
+            title_area: Rect::default(),
+            abstract_area: Rect::default(),
+            list_area: Rect::default(),
+            list_offset: 0,
+            picker_area: Rect::default(),
+            footer_buttons: Vec::new(),
+            pending_keys: Vec::new(),
+            workers: Workers::new(),
+            list_task: None,
+            start_fetch: None,
         }
     }
     
     pub fn set_results(&mut self, results: Vec<PRZZIResult>) {
+        self.loaded = results;
+        self.apply_view();
+    }
+
+    /// Sorts and filters the loaded papers into `results`, staying on the same paper when it is still shown
+    fn apply_view(&mut self) {
+        let selected = self.results.get(self.result_index).map(|result| result.url.clone());
+        let mut results: Vec<PRZZIResult> = self
+            .loaded
+            .iter()
+            .filter(|result| matches_filter(result, &self.filter))
+            .cloned()
+            .collect();
+        match self.sort {
+            ResultSort::Relevance => {},
+            ResultSort::Year => results.sort_by_key(|result| std::cmp::Reverse(result.year)),
+            ResultSort::Citations => results.sort_by_key(|result| std::cmp::Reverse(result.citation_count)),
+            ResultSort::Title => results.sort_by_key(|result| result.title.to_lowercase()),
+        }
         self.results = results;
+        match selected.and_then(|url| self.results.iter().position(|result| result.url == url)) {
+            Some(index) => self.result_index = index,
+            None => {
+                self.result_index = 0;
+                self.scroll = 0;
+                self.current_match = None;
+            }
+        }
     }
 
-    fn read_key(&mut self) -> Result<KeyEvent, PRZZIError> {
-        loop {
-            if poll(std::time::Duration::from_millis(100))? {
-                if let Key(key) = read()? {
-                    return Ok(key);
+    fn query_stems(&self) -> Vec<String> {
+        self.query.as_deref().map(przzi_highlight::query_stems).unwrap_or_default()
+    }
+
+    /// Scrolls the abstract to the next (or previous) word matching the query
+    fn jump_to_match(&mut self, forward: bool) {
+        let stems = self.query_stems();
+        if stems.is_empty() {
+            self.popup.open("There is no search query to jump to, search with / first".to_string(), "Info".to_string());
+            return;
+        }
+        // Line of every match, in order
+        let match_lines: Vec<usize> = self
+            .abstract_lines
+            .iter()
+            .enumerate()
+            .flat_map(|(line, text)| std::iter::repeat_n(line, przzi_highlight::count_matches(text, &stems)))
+            .collect();
+        if match_lines.is_empty() {
+            self.popup.open("The query does not appear in this abstract".to_string(), "Info".to_string());
+            return;
+        }
+        let count = match_lines.len();
+        let next = match (self.current_match, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(current), true) => (current + 1) % count,
+            (Some(current), false) => (current + count - 1) % count,
+        };
+        self.current_match = Some(next);
+        self.scroll_to_line(match_lines[next]);
+    }
+
+    /// Scrolls the abstract so a line of its text is near the top
+    fn scroll_to_line(&mut self, line: usize) {
+        // The text starts after a heading and an empty line, keep a line above it in view
+        self.scroll_to((line + 1) as u16);
+    }
+
+    /// How far the abstract can scroll before its last line reaches the bottom of the box
+    fn max_scroll(&self) -> u16 {
+        // The heading and the empty line under it come before the text
+        scroll_limit(self.abstract_lines.len() + 2, self.abstract_rows())
+    }
+
+    /// Rows of the abstract box inside its borders, as last drawn
+    fn abstract_rows(&self) -> u16 {
+        self.abstract_area.height.saturating_sub(2)
+    }
+
+    fn scroll_to(&mut self, scroll: u16) {
+        self.scroll = scroll.min(self.max_scroll());
+    }
+
+    /// How far the help overlay can scroll before its last line reaches the bottom
+    fn max_help_scroll(&self) -> u16 {
+        scroll_limit(self.help_text().len(), self.help_area.height.saturating_sub(2))
+    }
+
+    fn scroll_help_to(&mut self, scroll: u16) {
+        self.help_scroll = scroll.min(self.max_help_scroll());
+    }
+
+    /// Shows another paper of the list, from the start of its abstract
+    fn select(&mut self, index: usize) {
+        self.popup.close();
+        self.result_index = index;
+        self.scroll = 0;
+        self.current_match = None;
+        if let Some(find) = self.find.as_mut() {
+            find.current = 0;
+        }
+    }
+
+    fn found(&self) -> Vec<przzi_highlight::Found> {
+        match &self.find {
+            Some(find) => przzi_highlight::find_in_lines(&self.abstract_lines, &find.input),
+            None => Vec::new(),
+        }
+    }
+
+    /// Moves to another occurrence of the find text, `step` of 0 stays on the current one
+    fn jump_to_found(&mut self, step: isize) {
+        let found = self.found();
+        let count = found.last().map_or(0, |piece| piece.occurrence + 1);
+        let find = match self.find.as_mut() {
+            Some(find) => find,
+            None => return,
+        };
+        if count == 0 {
+            find.current = 0;
+            return;
+        }
+        find.current = (find.current as isize + step).rem_euclid(count as isize) as usize;
+        let current = find.current;
+        if let Some(piece) = found.iter().find(|piece| piece.occurrence == current) {
+            self.scroll_to_line(piece.line);
+        }
+    }
+
+    fn handle_find_key(&mut self, key: KeyEvent) {
+        let find = match self.find.as_mut() {
+            Some(find) => find,
+            None => return,
+        };
+        match key {
+            KeyEvent { code: KeyCode::Esc, .. } => self.find = None,
+            KeyEvent { code: KeyCode::Enter, .. } if find.editing => {
+                find.editing = false;
+                if find.input.is_empty() {
+                    self.find = None;
+                }
+            },
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT
+            } if find.editing => {
+                find.input.push(c);
+                find.current = 0;
+                self.jump_to_found(0);
+            },
+            KeyEvent { code: KeyCode::Backspace, .. } if find.editing => {
+                find.input.pop();
+                find.current = 0;
+                self.jump_to_found(0);
+            },
+            _ => {}
+        }
+    }
+
+    fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.apply_view();
+    }
+
+    /// What the current result list is, shown in the title bar
+    pub fn set_label(&mut self, label: String) {
+        self.label = label;
+    }
+
+    /// Query the results were searched with, highlighted in titles and abstracts
+    pub fn set_query(&mut self, query: String) {
+        self.query = Some(query);
+    }
+
+    /// Search to run in the background once the UI is up, its results become the list
+    pub fn set_search(&mut self, query: String, num_results: usize, filters: SearchFilters) {
+        self.query = Some(query.clone());
+        self.start_fetch = Some(StartFetch::Search(SearchRequest { query, num_results, filters }));
+    }
+
+    /// Paper to fetch once the UI is up and show on its own
+    pub fn set_paper(&mut self, paper: PaperId) {
+        self.start_fetch = Some(StartFetch::Paper(paper));
+    }
+
+    /// Name to look authors up by once the UI is up, they are offered to choose from
+    pub fn set_author_search(&mut self, name: String) {
+        self.start_fetch = Some(StartFetch::Authors(name));
+    }
+
+    /// Seed papers to fetch recommendations for once the UI is up, they start out liked / disliked
+    pub fn set_recommendation_seeds(&mut self, seeds: Vec<PaperId>, negative: Vec<PaperId>, limit: usize) {
+        self.start_fetch = Some(StartFetch::Recommendations { seeds, negative, limit });
+    }
+
+    /// Runs the saved watches once the UI is up and shows the papers they found that are new
+    pub fn set_watch_run(&mut self, name: Option<String>) {
+        self.start_fetch = Some(StartFetch::Watches(name));
+    }
+
+    /// Papers already liked / disliked, e.g. the seeds of `paperazzi recommend`
+    pub fn set_feedback(&mut self, liked: Vec<String>, disliked: Vec<String>) {
+        self.liked = liked;
+        self.disliked = disliked;
+    }
+
+    /// Keys to use instead of the default ones
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    /// Colors to draw with instead of the default dark theme
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Whether to capture the mouse for scrolling and clicking
+    pub fn set_mouse(&mut self, mouse: bool) {
+        self.mouse = mouse;
+    }
+
+    /// Style to show and copy citations in at first
+    pub fn set_citation_style(&mut self, style: CitationStyle) {
+        self.citation_style = style;
+    }
+
+    /// Papers to mark as new
+    pub fn set_new_papers(&mut self, new_papers: Vec<String>) {
+        self.new_papers = new_papers;
+    }
+
+    /// Marks the current paper as liked or disliked, pressing again clears the mark
+    fn toggle_feedback(&mut self, like: bool) {
+        let paper_id = match self.results.get(self.result_index).and_then(|result| result.paper_id.clone()) {
+            Some(paper_id) => paper_id,
+            None => {
+                self.popup.open("This paper is not on Semantic Scholar so it cant be used for recommendations :(".to_string(), "Error!".to_string());
+                return;
+            }
+        };
+        let (marked, other) = if like {
+            (&mut self.liked, &mut self.disliked)
+        } else {
+            (&mut self.disliked, &mut self.liked)
+        };
+        other.retain(|id| *id != paper_id);
+        if marked.contains(&paper_id) {
+            marked.retain(|id| *id != paper_id);
+        } else {
+            marked.push(paper_id);
+        }
+    }
+
+    /// Copies something about the current paper to the clipboard, `what` names it in the popup
+    fn copy(&mut self, what: &str, text: Option<String>) {
+        let text = match text {
+            Some(text) if !text.is_empty() => text,
+            _ => {
+                self.popup.open(format!("This paper has no {} to copy", what), "Info".to_string());
+                return;
+            }
+        };
+        let preview = text.lines().next().unwrap_or("");
+        match przzi_clipboard::copy(&text) {
+            Ok(Copied::Tool(tool)) => {
+                self.popup.open(format!("Copied the {} to the clipboard ({}) \n {}", what, tool, preview), "Success".to_string());
+            },
+            // There is no telling whether the terminal took it
+            Ok(Copied::Terminal) => {
+                self.popup.open(
+                    format!("Sent the {} to the terminal clipboard (OSC 52), if your terminal supports it \n {}", what, preview),
+                    "Info".to_string(),
+                );
+            },
+            Err(e) => {
+                self.popup.open(format!("Could not copy the {} :( \n {}", what, e.msg), "Error!".to_string());
+            }
+        }
+    }
+
+    fn copy_citation(&mut self) {
+        let citation = przzi_cite::format(&self.results[self.result_index], self.citation_style);
+        self.copy(&format!("{} citation", self.citation_style.name()), Some(citation));
+    }
+
+    /// Tab cycles through the styles, Enter copies the citation
+    fn handle_cite_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Tab | KeyCode::Right => self.citation_style = self.citation_style.next(),
+            KeyCode::Enter | KeyCode::Char('y') => {
+                self.cite_preview = false;
+                self.copy_citation();
+            },
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('C') => self.cite_preview = false,
+            _ => {}
+        }
+    }
+
+    /// Fetches a new result list in the background, dropping the one still being fetched if any
+    fn fetch_list<F>(&mut self, label: String, fetch: F)
+    where
+        F: FnOnce(&PRZZIClient) -> FetchedList + Send + 'static,
+    {
+        if let Some(id) = self.list_task.take() {
+            self.workers.cancel(id);
+        }
+        self.popup.close();
+        let client = self.client.clone();
+        self.list_task = Some(self.workers.spawn(label, move |reporter| {
+            reporter.send(Fetched::List(fetch(&client)));
+        }));
+    }
+
+    /// Starts fetching what the UI was set up to show
+    fn fetch_start(&mut self, start: StartFetch) {
+        match start {
+            StartFetch::Search(request) => self.search(request, true),
+            StartFetch::Paper(paper) => {
+                self.fetch_list(format!("Fetching {}", paper), move |client| FetchedList::Paper(client.paper(&paper).map(Box::new)));
+            },
+            StartFetch::Authors(name) => {
+                self.fetch_list(format!("Searching authors \"{}\"", name), move |client| {
+                    let results = client.search_authors(&name, AUTHOR_CANDIDATES);
+                    FetchedList::AuthorCandidates { name, results }
+                });
+            },
+            StartFetch::Recommendations { seeds, negative, limit } => {
+                self.fetch_list("Fetching recommendations".to_string(), move |client| {
+                    FetchedList::Seeded(recommend_from_seeds(client, &seeds, &negative, limit))
+                });
+            },
+            StartFetch::Watches(name) => {
+                self.fetch_list("Running saved watches".to_string(), move |client| {
+                    FetchedList::Watches(przzi_watch::run_watches(client, name.as_deref()))
+                });
+            },
+        }
+    }
+
+    /// Whether a result list is still being fetched
+    fn fetching_list(&self) -> bool {
+        self.workers.tasks().iter().any(|task| Some(task.id) == self.list_task)
+    }
+
+    /// Takes in what the background work sent since the last frame
+    fn receive(&mut self) {
+        for (id, fetched) in self.workers.poll() {
+            let fetched = match fetched {
+                Ok(fetched) => fetched,
+                Err(Failed { label, reason }) => {
+                    if self.list_task == Some(id) {
+                        self.list_task = None;
+                    }
+                    self.popup.open(format!("{} failed :( \n {}", label, reason), "Error!".to_string());
+                    continue;
+                }
+            };
+            match fetched {
+                Fetched::List(list) => {
+                    self.list_task = None;
+                    self.show_list(list);
+                },
+                Fetched::DownloadProgress(downloaded, total) => {
+                    self.workers.set_progress(id, przzi_download::format_progress(downloaded, total));
+                },
+                Fetched::Downloaded(result) => self.show_download(result),
+            }
+        }
+    }
+
+    /// Shows a fetched result list, or why there is none
+    fn show_list(&mut self, list: FetchedList) {
+        match list {
+            FetchedList::Search { request, replace, results } => match results {
+                Ok(results) => {
+                    let entry = HistoryEntry::new(request.query.clone(), request.filters, request.num_results, results.len());
+                    // Warned about like on the command line, without hiding the results
+                    let unsaved = przzi_history::record(&entry)
+                        .err()
+                        .map(|e| format!("Could not save the search history: {}", e.msg));
+                    if results.is_empty() {
+                        let mut msg = "No papers found for this query".to_string();
+                        if let Some(unsaved) = &unsaved {
+                            msg = format!("{} \n {}", msg, unsaved);
+                        }
+                        self.popup.open(msg, "Info".to_string());
+                        return;
+                    }
+                    if replace {
+                        self.set_results(results);
+                    } else {
+                        self.push_results(format!("Search: {}", request.query), results, None);
+                        self.query = Some(request.query);
+                    }
+                    if let Some(unsaved) = unsaved {
+                        self.popup.open(unsaved, "Error!".to_string());
+                    }
+                },
+                Err(e) => {
+                    self.popup.open(format!("Search failed :( \n {}", e.msg), "Error!".to_string());
+                }
+            },
+            FetchedList::Author(fetched) => match fetched {
+                Ok((_, papers)) if papers.is_empty() => {
+                    self.popup.open("No papers found for this author".to_string(), "Info".to_string());
+                },
+                Ok((profile, papers)) => {
+                    let label = format!("Author: {}", profile.name);
+                    self.push_results(label, papers, Some(AuthorView { profile }));
+                },
+                Err(e) => {
+                    self.popup.open(format!("Could not fetch the author :( \n {}", e.msg), "Error!".to_string());
+                }
+            },
+            FetchedList::Linked { link, label, results } => {
+                let name = link.name().to_lowercase();
+                match results {
+                    Ok(results) if results.is_empty() => {
+                        self.popup.open(format!("No {} found for this paper", name), "Info".to_string());
+                    },
+                    Ok(results) => self.push_results(label, results, None),
+                    Err(e) => {
+                        self.popup.open(format!("Could not fetch {} :( \n {}", name, e.msg), "Error!".to_string());
+                    }
+                }
+            },
+            FetchedList::Recommendations { label, results } => match results {
+                Ok(results) if results.is_empty() => {
+                    self.popup.open("No recommendations found, try liking other papers".to_string(), "Info".to_string());
+                },
+                Ok(results) => self.push_results(label, results, None),
+                Err(e) => {
+                    self.popup.open(format!("Could not fetch recommendations :( \n {}", e.msg), "Error!".to_string());
+                }
+            },
+            FetchedList::Paper(result) => match result {
+                Ok(result) => self.set_results(vec![*result]),
+                Err(e) => {
+                    self.popup.open(format!("Could not fetch the paper :( \n {}", e.msg), "Error!".to_string());
+                }
+            },
+            FetchedList::AuthorCandidates { name, results } => match results {
+                Ok(candidates) if candidates.is_empty() => {
+                    self.popup.open(format!("No authors found matching \"{}\"", name), "Info".to_string());
+                },
+                Ok(candidates) => {
+                    self.author_candidates = candidates;
+                    if let [only] = self.author_candidates.as_slice() {
+                        let author_id = only.author_id.clone();
+                        self.open_author(author_id);
+                    } else {
+                        self.pick_author_candidate();
+                    }
+                },
+                Err(e) => {
+                    self.popup.open(format!("Could not search for authors :( \n {}", e.msg), "Error!".to_string());
+                }
+            },
+            FetchedList::Seeded(fetched) => match fetched {
+                Ok(seeded) if seeded.results.is_empty() => {
+                    self.popup.open("No recommendations found for these papers".to_string(), "Info".to_string());
+                },
+                Ok(Seeded { liked, disliked, results }) => {
+                    self.label = format!("Recommended from {} seed(s)", liked.len());
+                    self.set_feedback(liked, disliked);
+                    self.set_results(results);
+                },
+                Err(e) => {
+                    self.popup.open(format!("Could not fetch recommendations :( \n {}", e.msg), "Error!".to_string());
+                }
+            },
+            FetchedList::Watches(reports) => self.show_watch_reports(reports),
+        }
+    }
+
+    /// Shows the papers the saved watches found that are new, and the watches that failed.
+    ///
+    /// The papers are only remembered as seen here, so a run cancelled with `Esc` loses none
+    fn show_watch_reports(&mut self, ran: Result<WatchRun, PRZZIError>) {
+        let ran = match ran {
+            Ok(ran) => ran,
+            Err(e) => {
+                self.popup.open(format!("Could not run the saved watches :( \n {}", e.msg), "Error!".to_string());
+                return;
+            }
+        };
+        let mut failures: Vec<String> = ran
+            .reports
+            .iter()
+            .filter_map(|report| {
+                let error = report.error.as_ref()?;
+                Some(format!("Watch \"{}\" failed: {}", report.watch, error))
+            })
+            .collect();
+        if let Err(e) = ran.save() {
+            failures.push(format!("Could not remember the papers as seen, they will be reported again: {}", e.msg));
+        }
+        let new: Vec<PRZZIResult> = ran.reports.into_iter().flat_map(|report| report.new).collect();
+        if new.is_empty() {
+            let mut lines = vec!["No new papers".to_string()];
+            lines.extend(failures);
+            self.popup.open(lines.join(" \n "), "Info".to_string());
+            return;
+        }
+        self.label = format!("{} new in saved watches", new.len());
+        self.set_new_papers(new.iter().filter_map(|paper| paper.paper_id.clone()).collect());
+        self.set_results(new);
+        if !failures.is_empty() {
+            self.popup.open(failures.join(" \n "), "Error!".to_string());
+        }
+    }
+
+    /// Downloads the current paper in the background, any number of downloads can run at once
+    fn start_download(&mut self) {
+        let result = &self.results[self.result_index];
+        let paper_id = match result.url.as_str().parse::<PaperId>() {
+            Ok(paper_id) => paper_id,
+            Err(_) => {
+                self.popup.open("This paper doesnt have a valid DOI or arXiv ID so a download isnt possible just yet :( If you think this is wrong, feel free to raise an issue \n Please try redirect instead".to_string(), "Error!".to_string());
+                return;
+            }
+        };
+        let label = format!("Downloading \"{}\"", shorten(&result.title, DOWNLOAD_TITLE_WIDTH));
+        self.popup.close();
+        self.workers.spawn(label, move |reporter| {
+            let mut last_sent = Instant::now();
+            let result = przzi_download::download_id(&paper_id, |downloaded, total| {
+                if last_sent.elapsed() >= PROGRESS_INTERVAL {
+                    reporter.send(Fetched::DownloadProgress(downloaded, total));
+                    last_sent = Instant::now();
+                }
+                !reporter.cancelled()
+            });
+            reporter.send(Fetched::Downloaded(result));
+        });
+    }
+
+    fn show_download(&mut self, result: Result<PathBuf, DownloadError>) {
+        match result {
+            Ok(path) => {
+                self.popup.open(format!("Download Complete :) !! \n Saved to {}", path.display()), "Success".to_string());
+            },
+            Err(DownloadError::NoDownloadLink) => {
+                self.popup.open("Download failed! This paper is not available to download yet :( if you think this is wrong, raise a issue :) \n Please try redirecting instead".to_string(), "Error!".to_string());
+            },
+            Err(e) => {
+                self.popup.open(format!("Download failed! {} :( \n Please try redirecting instead", e), "Error!".to_string());
+            }
+        }
+    }
+
+    /// Shows papers recommended from the liked and disliked papers as a new result list
+    fn open_recommendations(&mut self) {
+        if self.liked.is_empty() {
+            self.popup.open("Like some papers with + first, recommendations are built from them".to_string(), "Info".to_string());
+            return;
+        }
+        let liked = self.liked.clone();
+        let disliked = self.disliked.clone();
+        let label = format!("Recommended from {} liked, {} disliked", liked.len(), disliked.len());
+        self.fetch_list("Fetching recommendations".to_string(), move |client| {
+            let results = client.recommendations(&liked, &disliked, RECOMMENDATION_LIMIT);
+            FetchedList::Recommendations { label, results }
+        });
+    }
+
+    /// Shows a new result list, keeping the current one on the back-stack
+    fn push_results(&mut self, label: String, results: Vec<PRZZIResult>, author: Option<AuthorView>) {
+        // An author's papers are easiest to read newest first
+        let sort = if author.is_some() { ResultSort::Year } else { ResultSort::Relevance };
+        let frame = ResultFrame {
+            label: std::mem::replace(&mut self.label, label),
+            loaded: std::mem::replace(&mut self.loaded, results),
+            results: std::mem::take(&mut self.results),
+            result_index: self.result_index,
+            scroll: self.scroll,
+            author: std::mem::replace(&mut self.author, author),
+            sort: std::mem::replace(&mut self.sort, sort),
+            filter: std::mem::take(&mut self.filter),
+            query: self.query.take(),
+        };
+        self.back_stack.push(frame);
+        self.editing_filter = false;
+        self.result_index = 0;
+        self.scroll = 0;
+        self.apply_view();
+    }
+
+    /// Restores the previous result list with its selection and scroll
+    fn pop_results(&mut self) {
+        if let Some(frame) = self.back_stack.pop() {
+            self.label = frame.label;
+            self.loaded = frame.loaded;
+            self.results = frame.results;
+            self.result_index = frame.result_index;
+            self.scroll = frame.scroll;
+            self.author = frame.author;
+            self.sort = frame.sort;
+            self.filter = frame.filter;
+            self.query = frame.query;
+            self.editing_filter = false;
+            self.current_match = None;
+        }
+    }
+
+    /// Lets the user pick one of the authors matching `--author`
+    fn pick_author_candidate(&mut self) {
+        // Nothing found yet, or still loading
+        if self.author_candidates.is_empty() {
+            self.popup.open("There are no authors to choose from".to_string(), "Info".to_string());
+            return;
+        }
+        let authors = self
+            .author_candidates
+            .iter()
+            .map(|author| {
+                let mut label = author.name.clone();
+                if !author.affiliations.is_empty() {
+                    label.push_str(&format!(" · {}", author.affiliations.join("; ")));
+                }
+                if let Some(papers) = author.paper_count {
+                    label.push_str(&format!(" · {} papers", papers));
+                }
+                if let Some(h_index) = author.h_index {
+                    label.push_str(&format!(" · h-index {}", h_index));
+                }
+                (label, Some(author.author_id.clone()))
+            })
+            .collect();
+        self.picker = Some(AuthorPicker::new("Which author?".to_string(), authors));
+    }
+
+    /// Lets the user pick one of the authors of the current paper
+    fn pick_author(&mut self) {
+        let result = match self.results.get(self.result_index) {
+            Some(result) => result,
+            None => {
+                self.pick_author_candidate();
+                return;
+            }
+        };
+        if result.authors.is_empty() {
+            self.popup.open("This paper has no authors listed".to_string(), "Info".to_string());
+            return;
+        }
+        let authors = result
+            .authors
+            .iter()
+            .map(|author| {
+                let label = match author.author_id {
+                    Some(_) => author.name.clone(),
+                    None => format!("{} (not on Semantic Scholar)", author.name),
+                };
+                (label, author.author_id.clone())
+            })
+            .collect();
+        self.picker = Some(AuthorPicker::new("Authors".to_string(), authors));
+    }
+
+    fn handle_picker_key(&mut self, key: KeyEvent) {
+        // The keys for the next / previous paper move through the authors too
+        let action = match self.keymap.lookup(&[KeyChord::from_event(key)], false) {
+            KeyMatch::Action(action) => Some(action),
+            _ => None,
+        };
+        let picker = match self.picker.as_mut() {
+            Some(picker) => picker,
+            None => return,
+        };
+        match key.code {
+            _ if action == Some(Action::PreviousResult) => picker.previous(),
+            _ if action == Some(Action::NextResult) => picker.next(),
+            KeyCode::Up => picker.previous(),
+            KeyCode::Down => picker.next(),
+            KeyCode::Esc | KeyCode::Char('q') => self.picker = None,
+            KeyCode::Enter => self.choose_author(),
+            _ => {}
+        }
+    }
+
+    /// Opens the author selected in the picker
+    fn choose_author(&mut self) {
+        let picker = match self.picker.take() {
+            Some(picker) => picker,
+            None => return,
+        };
+        let index = picker.selected();
+        if index >= picker.authors.len() {
+            return;
+        }
+        let (_, author_id) = picker.authors[index].clone();
+        match author_id {
+            Some(author_id) => self.open_author(author_id),
+            None => self.popup.open("This author is not on Semantic Scholar so there is no profile to show :(".to_string(), "Error!".to_string()),
+        }
+    }
+
+    fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let prompt = match self.prompt.as_mut() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        match key {
+            KeyEvent {
+                code: KeyCode::Char('u'),
+                modifiers: KeyModifiers::CONTROL
+            } => prompt.recall(None),
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT
+            } => {
+                prompt.input.push(c);
+                prompt.recalled = None;
+            },
+            KeyEvent { code: KeyCode::Backspace, .. } => {
+                prompt.input.pop();
+                prompt.recalled = None;
+            },
+            KeyEvent { code: KeyCode::Up, .. } => prompt.older(),
+            KeyEvent { code: KeyCode::Down, .. } => prompt.newer(),
+            KeyEvent { code: KeyCode::Esc, .. } => self.prompt = None,
+            KeyEvent { code: KeyCode::Enter, .. } if !prompt.input.trim().is_empty() => {
+                let prompt = self.prompt.take().unwrap();
+                let request = SearchRequest {
+                    query: prompt.input.trim().to_string(),
+                    num_results: prompt.num_results,
+                    filters: prompt.filters,
+                };
+                self.search(request, false);
+            },
+            _ => {}
+        }
+    }
+
+    /// Narrows the list as the filter is typed
+    fn handle_filter_key(&mut self, key: KeyEvent) {
+        match key {
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT
+            } => {
+                self.filter.push(c);
+                self.apply_view();
+            },
+            KeyEvent { code: KeyCode::Backspace, .. } => {
+                self.filter.pop();
+                self.apply_view();
+            },
+            KeyEvent { code: KeyCode::Enter, .. } => self.editing_filter = false,
+            KeyEvent { code: KeyCode::Esc, .. } => {
+                self.editing_filter = false;
+                self.filter.clear();
+                self.apply_view();
+            },
+            _ => {}
+        }
+    }
+
+    /// Runs a search in the background, it is saved to the history once the results arrive
+    fn search(&mut self, request: SearchRequest, replace: bool) {
+        let label = format!("Searching \"{}\"", request.query);
+        self.fetch_list(label, move |client| {
+            let results = client.search(&Query::new(&request.query, request.num_results, &request.filters));
+            FetchedList::Search { request, replace, results }
+        });
+    }
+
+    /// Shows the profile and papers of an author as a new result list
+    fn open_author(&mut self, author_id: String) {
+        self.fetch_list("Fetching author".to_string(), move |client| {
+            FetchedList::Author(client.author(&author_id).and_then(|profile| {
+                let papers = client.author_papers(&author_id, AUTHOR_PAPER_LIMIT)?;
+                Ok((profile, papers))
+            }))
+        });
+    }
+
+    fn open_linked(&mut self, link: Link) {
+        let name = link.name();
+        let result = &self.results[self.result_index];
+        let paper_id = match &result.paper_id {
+            Some(paper_id) => paper_id.clone(),
+            None => {
+                self.popup.open("This paper is not on Semantic Scholar so its citations cant be followed :(".to_string(), "Error!".to_string());
+                return;
+            }
+        };
+        let label = format!("{} of \"{}\"", name, result.title);
+        self.fetch_list(format!("Fetching {}", name.to_lowercase()), move |client| {
+            let results = match link {
+                Link::References => client.references(&paper_id, LINK_LIMIT),
+                Link::Citations => client.citations(&paper_id, LINK_LIMIT),
+            };
+            FetchedList::Linked { link, label, results }
+        });
+    }
+
+    /// The action bound to the keys pressed so far, `None` while a longer binding is being typed
+    fn next_action(&mut self, key: KeyEvent) -> Option<Action> {
+        self.pending_keys.push(KeyChord::from_event(key));
+        let finding = self.find.is_some();
+        match self.keymap.lookup(&self.pending_keys, finding) {
+            KeyMatch::Action(action) => {
+                self.pending_keys.clear();
+                Some(action)
+            },
+            KeyMatch::Pending => None,
+            KeyMatch::None => {
+                // A sequence that went nowhere, its last key may start another one
+                let retry = self.pending_keys.len() > 1;
+                self.pending_keys.clear();
+                if retry {
+                    self.next_action(key)
+                } else {
+                    None
                 }
             }
         }
     }
+
+    fn handle_help_key(&mut self, key: KeyEvent) {
+        let action = match self.keymap.lookup(&[KeyChord::from_event(key)], false) {
+            KeyMatch::Action(action) => Some(action),
+            _ => None,
+        };
+        match (key.code, action) {
+            (_, Some(Action::Help | Action::ClosePopup)) | (KeyCode::Esc | KeyCode::Char('q'), _) => {
+                self.show_help = false;
+            },
+            (KeyCode::Down, _) | (_, Some(Action::ScrollDown | Action::NextResult)) => {
+                self.scroll_help_to(self.help_scroll.saturating_add(1));
+            },
+            (KeyCode::Up, _) | (_, Some(Action::ScrollUp | Action::PreviousResult)) => {
+                self.scroll_help_to(self.help_scroll.saturating_sub(1));
+            },
+            _ => {}
+        }
+    }
+
+    /// The next key press, mouse event or resize, `None` when there was none for a tick.
+    /// A resize needs nothing but the redraw that follows it
+    fn read_event(&mut self) -> Result<Option<Event>, PRZZIError> {
+        if poll(TICK)? {
+            return Ok(Some(read()?));
+        }
+        Ok(None)
+    }
+
+    /// Scrolls or clicks. Returns the action of a clicked footer hint or of the wheel over the title,
+    /// everything else is handled here
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> Option<Action> {
+        let at = |area: Rect| {
+            mouse.column >= area.x && mouse.column < area.x + area.width
+                && mouse.row >= area.y && mouse.row < area.y + area.height
+        };
+        if self.show_help {
+            match mouse.kind {
+                MouseEventKind::ScrollDown => self.scroll_help_to(self.help_scroll.saturating_add(MOUSE_SCROLL_LINES)),
+                MouseEventKind::ScrollUp => self.scroll_help_to(self.help_scroll.saturating_sub(MOUSE_SCROLL_LINES)),
+                MouseEventKind::Down(MouseButton::Left) => self.show_help = false,
+                _ => {}
+            }
+            return None;
+        }
+        if self.cite_preview {
+            if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+                self.cite_preview = false;
+            }
+            return None;
+        }
+        if let Some(picker) = self.picker.as_mut() {
+            // Rows inside the border
+            let rows = Rect {
+                x: self.picker_area.x + 1,
+                y: self.picker_area.y + 1,
+                width: self.picker_area.width.saturating_sub(2),
+                height: self.picker_area.height.saturating_sub(2),
+            };
+            match mouse.kind {
+                MouseEventKind::ScrollDown => picker.next(),
+                MouseEventKind::ScrollUp => picker.previous(),
+                MouseEventKind::Down(MouseButton::Left) if at(rows) => {
+                    let index = picker.offset + (mouse.row - rows.y) as usize;
+                    if index < picker.authors.len() {
+                        picker.state.select(Some(index));
+                        self.choose_author();
+                    }
+                },
+                MouseEventKind::Down(MouseButton::Left) if !at(self.picker_area) => self.picker = None,
+                _ => {}
+            }
+            return None;
+        }
+        if self.prompt.is_some() || self.editing_filter {
+            return None;
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollDown if at(self.abstract_area) => {
+                self.scroll_to(self.scroll.saturating_add(MOUSE_SCROLL_LINES));
+            },
+            MouseEventKind::ScrollUp if at(self.abstract_area) => {
+                self.scroll_to(self.scroll.saturating_sub(MOUSE_SCROLL_LINES));
+            },
+            MouseEventKind::ScrollDown if at(self.title_area) || at(self.list_area) => return Some(Action::NextResult),
+            MouseEventKind::ScrollUp if at(self.title_area) || at(self.list_area) => return Some(Action::PreviousResult),
+            MouseEventKind::Down(MouseButton::Left) if at(self.list_area) => {
+                // Rows inside the border
+                let row = mouse.row.saturating_sub(self.list_area.y + 1) as usize;
+                let index = self.list_offset + row;
+                if mouse.row > self.list_area.y && index < self.results.len() {
+                    self.select(index);
+                }
+            },
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some((_, action)) = self.footer_buttons.iter().find(|(area, _)| at(*area)) {
+                    return Some(*action);
+                }
+                // Clicking anywhere else dismisses a popup
+                self.popup.close();
+            },
+            _ => {}
+        }
+        None
+    }
     
+    /// Where everything goes for the terminal size, `None` when it is too small to show anything.
+    /// Wide terminals get the result list next to the paper, tall ones above it, small ones only the paper
+    fn layout(&self, size: Rect) -> Option<ScreenLayout> {
+        if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+            return None;
+        }
+        let screen = Layout::default()
+            .margin(1)
+            .constraints([Constraint::Min(0)])
+            .split(size)[0];
+        let footer_rows = self.footer_rows(screen.width);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(footer_rows)])
+            .split(screen);
+        let (list, paper) = if screen.width >= SIDE_BY_SIDE_WIDTH {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(LIST_PERCENT), Constraint::Percentage(100 - LIST_PERCENT)])
+                .split(rows[0]);
+            (Some(columns[0]), columns[1])
+        } else if screen.height >= STACKED_LIST_HEIGHT && self.results.len() > 1 {
+            let stacked = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(STACKED_LIST_ROWS), Constraint::Min(0)])
+                .split(rows[0]);
+            (Some(stacked[0]), stacked[1])
+        } else {
+            (None, rows[0])
+        };
+        let author_rows = if self.author.is_some() { AUTHOR_ROWS } else { 0 };
+        // The details get all the rows they need as long as the abstract keeps at least half
+        let title_rows = self.title_rows(paper.width).min(paper.height.saturating_sub(author_rows) / 2);
+        let parts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(author_rows),
+                Constraint::Length(title_rows),
+                Constraint::Min(0),
+            ])
+            .split(paper);
+        Some(ScreenLayout {
+            author: self.author.as_ref().map(|_| parts[0]),
+            list,
+            title: parts[1],
+            abstract_text: parts[2],
+            footer: rows[1],
+        })
+    }
+
+    fn draw_too_small<'a>(&'a self, size: Rect) -> Paragraph<'a> {
+        let text = vec![
+            Spans::from(Span::styled("Terminal too small", self.theme.title())),
+            Spans::from(Span::styled(
+                format!("{}×{}, needs {}×{}", size.width, size.height, MIN_WIDTH, MIN_HEIGHT),
+                self.theme.text()
+            )),
+        ];
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .wrap(Wrap {trim: true})
+    }
+
+    /// The papers of the current list around the selected one, `height` rows of them
+    fn draw_list<'a>(&'a self, height: usize) -> List<'a> {
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .skip(self.list_offset)
+            .take(height)
+            .map(|result| {
+                let year = result.year.map(|year| year.to_string()).unwrap_or_else(|| "----".to_string());
+                ListItem::new(Spans::from(vec![
+                    Span::styled(format!("{} ", year), self.theme.accent()),
+                    Span::styled(result.title.as_str(), self.theme.text()),
+                ]))
+            })
+            .collect();
+        List::new(items)
+            .block(
+                Block::default()
+                .title(Span::styled(format!("{} papers", self.results.len()), self.theme.title()))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Double)
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ")
+    }
+
     fn draw<B> (&mut self, rect: &mut Frame<B>)
     where
         B: Backend,
     {
         let size = rect.size();
-        let block = Block::default().style(Style::default().bg(Color::Rgb(40, 40, 40)).fg(Color::LightBlue));
+        let block = Block::default().style(self.theme.background());
         rect.render_widget(block, size);
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(1)
-            .constraints(
-                [
-                    Constraint::Percentage(25),
-                    Constraint::Percentage(65),
-                    Constraint::Percentage(10)
-                ]
-            )
-            .split(size);
+        let layout = match self.layout(size) {
+            Some(layout) => layout,
+            None => {
+                // Nothing to click or scroll until there is room again
+                self.title_area = Rect::default();
+                self.abstract_area = Rect::default();
+                self.list_area = Rect::default();
+                self.footer_buttons.clear();
+                let message = self.draw_too_small(size);
+                let middle = Rect::new(size.x, size.y + size.height.saturating_sub(2) / 2, size.width, size.height.min(2));
+                rect.render_widget(message, middle);
+                return;
+            }
+        };
+        if let Some(area) = layout.author {
+            let profile = self.draw_author();
+            rect.render_widget(profile, area);
+        }
+        self.list_area = layout.list.unwrap_or_default();
+        if let Some(area) = layout.list {
+            let height = area.height.saturating_sub(2) as usize;
+            self.list_offset = keep_in_view(self.list_offset, self.result_index, height);
+            let list = self.draw_list(height);
+            let mut state = ListState::default();
+            if !self.results.is_empty() {
+                state.select(Some(self.result_index - self.list_offset));
+            }
+            rect.render_stateful_widget(list, area, &mut state);
+        }
+        self.title_area = layout.title;
+        self.abstract_area = layout.abstract_text;
         let title = self.draw_title();
-        rect.render_widget(title, chunks[0]);
+        rect.render_widget(title, layout.title);
+        self.abstract_lines = match self.results.get(self.result_index) {
+            Some(result) if !result.abs.is_empty() => {
+                przzi_highlight::wrap_words(&result.abs, layout.abstract_text.width.saturating_sub(2) as usize)
+            },
+            _ => vec!["No abstract available".to_string()],
+        };
+        // The box may have grown or the text got shorter since the last scroll
+        self.scroll = self.scroll.min(self.max_scroll());
         let abs = self.draw_abstract();
-        rect.render_widget(abs, chunks[1]);
-        let footer = self.draw_footer();
-        rect.render_widget(footer, chunks[2]);
+        rect.render_widget(abs, layout.abstract_text);
+        let thumb = ScrollThumb {
+            scroll: self.scroll,
+            max_scroll: self.max_scroll(),
+            style: self.theme.accent(),
+        };
+        rect.render_widget(thumb, layout.abstract_text);
+        self.footer_buttons.clear();
+        if self.prompt.is_some() {
+            let prompt = self.draw_prompt();
+            rect.render_widget(prompt, layout.footer);
+        } else if self.editing_filter {
+            let filter = self.draw_filter();
+            rect.render_widget(filter, layout.footer);
+        } else if self.find.as_ref().is_some_and(|find| find.editing) {
+            let find = self.draw_find();
+            rect.render_widget(find, layout.footer);
+        } else {
+            let (footer, buttons) = self.draw_footer(layout.footer);
+            rect.render_widget(footer, layout.footer);
+            self.footer_buttons = buttons;
+        }
+        if let Some(picker) = self.picker.as_mut() {
+            let area = self.popup.centered_rect(60, 50, size);
+            picker.scroll_into_view(area.height.saturating_sub(2) as usize);
+            self.picker_area = area;
+            let items: Vec<ListItem> = picker.authors.iter().map(|(label, _)| ListItem::new(label.as_str())).collect();
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                    .title(Span::styled(picker.title.as_str(), self.theme.popup_title("Info")))
+                    .borders(Borders::ALL)
+                    .style(self.theme.popup())
+                )
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+                .highlight_symbol("> ");
+            rect.render_widget(Clear, area);
+            rect.render_stateful_widget(list, area, &mut picker.state);
+        }
+        if self.cite_preview {
+            let cite = self.draw_cite();
+            let area = self.popup.centered_rect(80, 40, size);
+            rect.render_widget(Clear, area);
+            rect.render_widget(cite, area);
+        }
+        if self.show_help {
+            let area = self.popup.centered_rect(80, 80, size);
+            self.help_area = area;
+            // The overlay may have grown since the last scroll
+            self.scroll_help_to(self.help_scroll);
+            let help = self.draw_help();
+            rect.render_widget(Clear, area);
+            rect.render_widget(help, area);
+        }
         if self.popup.show_popup{
-            let para = self.popup.get_para(); 
+            let para = self.popup.get_para(&self.theme);
             let area = self.popup.centered_rect(80, 30, size);
             rect.render_widget(Clear, area);
             rect.render_widget(para, area);
@@ -160,32 +1758,111 @@ impl PRZZITUI {
 
     }
     
+    fn draw_author<'a>(&'a self) -> Paragraph<'a> {
+        let text_style = self.theme.text();
+        let author = match &self.author {
+            Some(author) => author,
+            None => return Paragraph::new(""),
+        };
+        let profile = &author.profile;
+        let mut stats: Vec<String> = Vec::new();
+        if let Some(papers) = profile.paper_count {
+            stats.push(format!("Papers: {}", papers));
+        }
+        if let Some(citations) = profile.citation_count {
+            stats.push(format!("Citations: {}", citations));
+        }
+        if let Some(h_index) = profile.h_index {
+            stats.push(format!("h-index: {}", h_index));
+        }
+        let affiliations = if profile.affiliations.is_empty() {
+            "No affiliations listed".to_string()
+        } else {
+            profile.affiliations.join("; ")
+        };
+        let text = vec![
+            Spans::from(Span::styled(affiliations, text_style.add_modifier(Modifier::ITALIC))),
+            Spans::from(Span::styled(stats.join("    "), text_style)),
+        ];
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                .title(Span::styled(profile.name.as_str(), self.theme.title()))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Double)
+            )
+            .wrap(Wrap {trim: true})
+    }
+
+    /// The label with the position in the list and any sort or filter in use
+    fn title_label(&self) -> String {
+        let mut label = self.label.clone();
+        if !self.results.is_empty() {
+            label.push_str(&format!(" ({}/{})", self.result_index + 1, self.results.len()));
+        }
+        if self.sort != ResultSort::Relevance {
+            label.push_str(&format!(" · by {}", self.sort.name()));
+        }
+        if !self.filter.is_empty() {
+            label.push_str(&format!(" · filter \"{}\" ({} of {})", self.filter, self.results.len(), self.loaded.len()));
+        }
+        label
+    }
+
     fn draw_title<'a>(&'a self) -> Paragraph<'a> {
+        let text_style = self.theme.text();
+        let result = match self.results.get(self.result_index) {
+            Some(result) => result,
+            None => {
+                let hint = if self.fetching_list() {
+                    "Fetching papers..."
+                } else if !self.filter.is_empty() {
+                    "No papers match the filter"
+                } else if self.author_candidates.is_empty() {
+                    "No papers to show"
+                } else {
+                    "No author chosen yet, press a to choose one"
+                };
+                return Paragraph::new(Span::styled(hint, text_style))
+                    .alignment(Alignment::Center)
+                    .block(
+                        Block::default()
+                        .title(Span::styled(
+                            format!("Paperazzi · {}", self.title_label()),
+                            self.theme.title()
+                        ))
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Double)
+                    )
+                    .wrap(Wrap {trim: true});
+            }
+        };
+        let [year_venue, authors, details] = self.paper_details(result);
+        let stems = self.query_stems();
         let text = vec![
-            Spans::from(Span::styled(
-                self.results[self.result_index].title.as_str(),
-                Style::default()
-                .fg(Color::Rgb(213, 196, 161))
-                .add_modifier(Modifier::ITALIC)
+            Spans::from(przzi_highlight::highlight(
+                result.title.as_str(),
+                &stems,
+                text_style.add_modifier(Modifier::ITALIC),
+                self.theme.highlight().add_modifier(Modifier::ITALIC),
+                None,
+                &mut 0
             )),
             Spans::from("\n\n"),
-            Spans::from(Span::styled(
-                self.results[self.result_index].year.to_string(),
-                Style::default().fg(Color::Rgb(213, 196, 161))
-            )),
+            Spans::from(Span::styled(year_venue, text_style)),
             Spans::from("\n\n"),
-            Spans::from(Span::styled(
-                self.results[self.result_index].authors.join(", "),
-                Style::default().fg(Color::Rgb(213, 196, 161))
-            ))
+            Spans::from(Span::styled(authors, text_style)),
+            Spans::from("\n\n"),
+            Spans::from(Span::styled(details, text_style)),
         ];
-        Paragraph::new(text.clone())
+        Paragraph::new(text)
             .alignment(Alignment::Center)
             .block(
                 Block::default()
                 .title(Span::styled(
-                    "Paperazzi",
-                    Style::default().fg(Color::Red)
+                    format!("Paperazzi · {}", self.title_label()),
+                    self.theme.title()
                 ))
                 .borders(Borders::ALL)
                 .border_type(BorderType::Double)
@@ -193,213 +1870,700 @@ impl PRZZITUI {
             .wrap(Wrap {trim: true})    
     }
 
+    /// The lines under a paper's title: year and venue, the first authors, and counts, IDs and marks
+    fn paper_details(&self, result: &PRZZIResult) -> [String; 3] {
+        let mut year_venue: Vec<String> = Vec::new();
+        if let Some(year) = result.year {
+            year_venue.push(year.to_string());
+        }
+        if !result.venue.is_empty() {
+            year_venue.push(result.venue.clone());
+        }
+        let mut authors = result.authors.iter().take(4).map(|author| author.name.as_str()).collect::<Vec<&str>>().join(", ");
+        if result.authors.len() > 4 {
+            authors.push_str(", et al.");
+        }
+        let mut details: Vec<String> = Vec::new();
+        if let Some(citations) = result.citation_count {
+            details.push(format!("Citations: {}", citations));
+        }
+        if let Some(references) = result.reference_count {
+            details.push(format!("References: {}", references));
+        }
+        if let Some(doi) = &result.doi {
+            details.push(format!("DOI: {}", doi));
+        }
+        if let Some(arxiv) = &result.arxiv {
+            details.push(format!("arXiv: {}", arxiv));
+        }
+        if let Some(paper_id) = &result.paper_id {
+            if self.new_papers.contains(paper_id) {
+                details.push("[new]".to_string());
+            }
+            if self.liked.contains(paper_id) {
+                details.push("[+] Liked".to_string());
+            } else if self.disliked.contains(paper_id) {
+                details.push("[-] Disliked".to_string());
+            }
+        }
+        [year_venue.join(" · "), authors, details.join("    ")]
+    }
+
+    /// Rows the details box needs at a given width to show the whole title and details, borders included
+    fn title_rows(&self, width: u16) -> u16 {
+        let result = match self.results.get(self.result_index) {
+            Some(result) => result,
+            None => return 3,
+        };
+        let inner = width.saturating_sub(2).max(1) as usize;
+        let [year_venue, authors, details] = self.paper_details(result);
+        let text_rows: usize = [result.title.as_str(), &year_venue, &authors, &details]
+            .iter()
+            .map(|line| przzi_highlight::wrap_words(line, inner).len().max(1))
+            .sum();
+        // Three empty lines between the four and the borders
+        (text_rows + 3 + 2) as u16
+    }
+
     
+    /// The abstract comes already broken into lines by `draw` so matches can be scrolled to
     fn draw_abstract<'a>(&'a self) -> Paragraph<'a> {
-        let text = vec![
+        let text_style = self.theme.text();
+        let highlight = self.theme.highlight();
+        let stems = self.query_stems();
+        let mut counter = 0;
+        let mut text = vec![
             Spans::from(Span::styled(
                 "Abstract",
-                Style::default()
-                .fg(Color::Rgb(213, 196, 161))
-                .add_modifier(Modifier::ITALIC)
+                text_style.add_modifier(Modifier::ITALIC)
             )),
             Spans::from("\n\n"),
-            Spans::from(Span::styled(
-                self.results[self.result_index].abs.to_string(),
-                Style::default().fg(Color::Rgb(213, 196, 161))
-            )),
         ];
-        Paragraph::new(text.clone())
+        let found = self.found();
+        for (index, line) in self.abstract_lines.iter().enumerate() {
+            // While finding, only the found text is highlighted
+            let spans = match &self.find {
+                Some(find) => przzi_highlight::mark_found(
+                    line,
+                    index,
+                    &found,
+                    text_style,
+                    self.theme.found(),
+                    (find.current, self.theme.found_current())
+                ),
+                None => przzi_highlight::highlight(
+                    line,
+                    &stems,
+                    text_style,
+                    highlight,
+                    self.current_match.map(|index| (index, highlight.add_modifier(Modifier::REVERSED))),
+                    &mut counter
+                ),
+            };
+            text.push(Spans::from(spans));
+        }
+        let found_count = found.last().map_or(0, |piece| piece.occurrence + 1);
+        let title = match (&self.find, counter, self.current_match) {
+            (Some(find), _, _) if found_count > 0 => {
+                format!("Paperazzi · \"{}\" {}/{}", find.input, find.current.min(found_count - 1) + 1, found_count)
+            },
+            (Some(find), _, _) => format!("Paperazzi · \"{}\" not found", find.input),
+            (None, 0, _) => "Paperazzi".to_string(),
+            (None, count, Some(current)) if current < count => format!("Paperazzi · match {}/{}", current + 1, count),
+            (None, count, _) => format!("Paperazzi · {} matches", count),
+        };
+        Paragraph::new(text)
             .alignment(Alignment::Center)
             .block(
                 Block::default()
                 .title(Span::styled(
-                    "Paperazzi",
-                    Style::default().fg(Color::Red)
+                    title,
+                    self.theme.title()
                 ))
                 .borders(Borders::ALL)
                 .border_type(BorderType::Double)
             )
-            .wrap(Wrap {trim: true})
             .scroll((self.scroll, 0))
     }
 
     
-    fn draw_footer<'a>(&'a self) -> Paragraph<'a> {
-        let mut text = vec![Spans::from(vec![
-            Span::styled(
-                "p: ",
-                Style::default()
-                .fg(Color::Rgb(213, 196, 161))
-            ),
-            Span::styled(
-                "Previous",
-                Style::default().fg(Color::Green)
-            ),
-            Span::raw(
-                "    ",
-            ),
-            Span::styled(
-                "n: ",
-                Style::default()
-                .fg(Color::Rgb(213, 196, 161))
-            ),
-            Span::styled(
-                "Next",
-                Style::default().fg(Color::Green)
-            ),
-            Span::raw(
-                "    ",
-            ),
-            Span::styled(
-                "Ctrl-r: ",
-                Style::default()
-                .fg(Color::Rgb(213, 196, 161))
-            ),
-            Span::styled(
-                "Open in browser",
-                Style::default().fg(Color::Green)
-            ),
-            Span::raw(
-                "    ",
-            ),
-            Span::styled(
-                "Ctrl-d: ",
-                Style::default()
-                .fg(Color::Rgb(213, 196, 161))
-            ),
-            Span::styled(
-                "Download paper",
-                Style::default().fg(Color::Green)
-            ),
-            Span::raw(
-                "    "
-            ),
-            Span::styled(
-                "↓/↑: ",
-                Style::default().fg(Color::Rgb(213, 196, 161))
-            ),
-            Span::styled(
-                "Scroll Abstract",
-                Style::default().fg(Color::Green)
+    fn draw_prompt<'a>(&'a self) -> Paragraph<'a> {
+        let prompt = match &self.prompt {
+            Some(prompt) => prompt,
+            None => return Paragraph::new(""),
+        };
+        let text_style = self.theme.text();
+        let mut details: Vec<String> = Vec::new();
+        if let Some(entry) = prompt.recalled.and_then(|index| prompt.history.get(index)) {
+            details.push(format!("Searched {} · {} results", entry.local_time(), entry.result_count));
+        }
+        let filters = prompt.filters.describe();
+        if !filters.is_empty() {
+            details.push(filters);
+        }
+        let text = vec![
+            Spans::from(vec![
+                Span::styled("Search: ", self.theme.accent()),
+                Span::styled(format!("{}█", prompt.input), text_style),
+            ]),
+            Spans::from(Span::styled(details.join("    "), text_style.add_modifier(Modifier::ITALIC))),
+            Spans::from(vec![
+                Span::styled("Enter: ", text_style),
+                Span::styled("Search", self.theme.accent()),
+                Span::raw("    "),
+                Span::styled("↑/↓: ", text_style),
+                Span::styled("History", self.theme.accent()),
+                Span::raw("    "),
+                Span::styled("Ctrl-u: ", text_style),
+                Span::styled("Clear", self.theme.accent()),
+                Span::raw("    "),
+                Span::styled("Esc: ", text_style),
+                Span::styled("Cancel", self.theme.accent()),
+            ]),
+        ];
+        Paragraph::new(text)
+            .wrap(Wrap {trim: false})
+    }
+
+    fn draw_find<'a>(&'a self) -> Paragraph<'a> {
+        let find = match &self.find {
+            Some(find) => find,
+            None => return Paragraph::new(""),
+        };
+        let text_style = self.theme.text();
+        let count = self.found().last().map_or(0, |piece| piece.occurrence + 1);
+        let text = vec![
+            Spans::from(vec![
+                Span::styled("Find: ", self.theme.accent()),
+                Span::styled(format!("{}█", find.input), text_style),
+            ]),
+            Spans::from(Span::styled(
+                format!("{} matches in the abstract", count),
+                text_style.add_modifier(Modifier::ITALIC)
+            )),
+            Spans::from(vec![
+                Span::styled("Enter: ", text_style),
+                Span::styled("Done", self.theme.accent()),
+                Span::raw("    "),
+                Span::styled("Esc: ", text_style),
+                Span::styled("Cancel", self.theme.accent()),
+            ]),
+        ];
+        Paragraph::new(text)
+            .wrap(Wrap {trim: false})
+    }
+
+    fn draw_filter<'a>(&'a self) -> Paragraph<'a> {
+        let text_style = self.theme.text();
+        let text = vec![
+            Spans::from(vec![
+                Span::styled("Filter: ", self.theme.accent()),
+                Span::styled(format!("{}█", self.filter), text_style),
+            ]),
+            Spans::from(Span::styled(
+                format!("{} of {} papers match", self.results.len(), self.loaded.len()),
+                text_style.add_modifier(Modifier::ITALIC)
+            )),
+            Spans::from(vec![
+                Span::styled("Enter: ", text_style),
+                Span::styled("Keep filter", self.theme.accent()),
+                Span::raw("    "),
+                Span::styled("Esc: ", text_style),
+                Span::styled("Clear filter", self.theme.accent()),
+            ]),
+        ];
+        Paragraph::new(text)
+            .wrap(Wrap {trim: false})
+    }
+
+    /// Footer hints for the keys of the active keymap, leaving out actions without a key.
+    /// Actions given together share one hint, e.g. `↓/↑: Scroll Abstract`.
+    /// A click on a key does its action and a click on the description does the first one
+    fn footer_line(&self, hints: Vec<(Vec<Action>, String)>) -> FooterLine {
+        let mut spans: Vec<Span> = Vec::new();
+        let mut buttons: Vec<(u16, u16, Action)> = Vec::new();
+        let mut width: u16 = 0;
+        for (actions, description) in hints {
+            let keys = actions
+                .iter()
+                .filter_map(|action| self.keymap.hint(*action).map(|keys| (keys, *action)))
+                .collect::<Vec<(String, Action)>>();
+            let first = match keys.first() {
+                Some((_, action)) => *action,
+                None => continue,
+            };
+            let mut push = |spans: &mut Vec<Span<'static>>, span: Span<'static>, action: Option<Action>| {
+                let span_width = span.width() as u16;
+                if let Some(action) = action {
+                    buttons.push((width, span_width, action));
+                }
+                width += span_width;
+                spans.push(span);
+            };
+            if !spans.is_empty() {
+                push(&mut spans, Span::raw("    "), None);
+            }
+            for (index, (keys, action)) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    push(&mut spans, Span::styled("/", self.theme.text()), None);
+                }
+                push(&mut spans, Span::styled(keys, self.theme.text()), Some(action));
+            }
+            push(&mut spans, Span::styled(": ", self.theme.text()), None);
+            push(&mut spans, Span::styled(description, self.theme.accent()), Some(first));
+        }
+        FooterLine {
+            text: Spans::from(spans),
+            buttons,
+        }
+    }
+
+    /// Where on the screen the hints of centered footer lines end up. Lines too long for the footer
+    /// wrap, their hints cant be clicked
+    fn place_buttons(lines: &[FooterLine], area: Rect) -> Vec<(Rect, Action)> {
+        let mut placed = Vec::new();
+        let mut y = area.y;
+        for line in lines {
+            let width = line.text.width() as u16;
+            if width <= area.width {
+                let x = area.x + (area.width - width) / 2;
+                for (start, button_width, action) in &line.buttons {
+                    placed.push((Rect::new(x + start, y, *button_width, 1), *action));
+                }
+                y += 1;
+            } else {
+                y += width.div_ceil(area.width.max(1));
+            }
+        }
+        placed.retain(|(button, _)| button.y < area.y + area.height);
+        placed
+    }
+
+    /// Every key by where it can be used, for the help overlay
+    fn help_sections(&self) -> Vec<(&'static str, Vec<(String, String)>)> {
+        let bound = |actions: &[Action]| -> Vec<(String, String)> {
+            actions
+                .iter()
+                .map(|action| (self.keymap.keys(*action).join(", "), action.description().to_string()))
+                .filter(|(keys, _)| !keys.is_empty())
+                .collect()
+        };
+        let typed = |keys: &[(&str, &str)]| -> Vec<(String, String)> {
+            keys.iter().map(|(keys, description)| (keys.to_string(), description.to_string())).collect()
+        };
+        vec![
+            ("Result list", bound(&[
+                Action::NextResult,
+                Action::PreviousResult,
+                Action::FirstResult,
+                Action::LastResult,
+                Action::Sort,
+                Action::Filter,
+                Action::Search,
+                Action::Back,
+                Action::References,
+                Action::Citations,
+                Action::Authors,
+                Action::Like,
+                Action::Dislike,
+                Action::Recommend,
+                Action::ClosePopup,
+                Action::Cancel,
+                Action::Help,
+                Action::Quit,
+            ])),
+            ("Paper details", bound(&[
+                Action::ScrollDown,
+                Action::ScrollUp,
+                Action::PageDown,
+                Action::PageUp,
+                Action::ScrollToTop,
+                Action::ScrollToBottom,
+                Action::OpenInBrowser,
+                Action::Download,
+                Action::CopyDoi,
+                Action::CopyUrl,
+                Action::CopyTitle,
+                Action::CopyBibtex,
+                Action::CopyCitation,
+                Action::Cite,
+                Action::NextMatch,
+                Action::PreviousMatch,
+                Action::Find,
+            ])),
+            ("While finding in the abstract", bound(&[
+                Action::FindNext,
+                Action::FindPrevious,
+                Action::StopFind,
+            ])),
+            ("Search prompt", typed(&[
+                ("Enter", "Search"),
+                ("↑, ↓", "Older / newer past search"),
+                ("Ctrl-u", "Clear"),
+                ("Esc", "Cancel"),
+            ])),
+            ("Typing a filter or find text", typed(&[
+                ("Enter", "Keep it"),
+                ("Esc", "Clear it"),
+            ])),
+            ("Citation preview", typed(&[
+                ("Tab", "Next style"),
+                ("Enter", "Copy"),
+                ("Esc", "Close"),
+            ])),
+            ("Choosing an author", typed(&[
+                ("↑, ↓", "Move"),
+                ("Enter", "Open the author"),
+                ("Esc, q", "Close"),
+            ])),
+        ]
+    }
+
+    /// The lines of the help overlay, a heading and the keys of each section
+    fn help_text(&self) -> Vec<Spans<'static>> {
+        let sections = self.help_sections();
+        let width = sections
+            .iter()
+            .flat_map(|(_, keys)| keys.iter().map(|(keys, _)| keys.chars().count()))
+            .max()
+            .unwrap_or(0);
+        let mut text: Vec<Spans> = Vec::new();
+        for (title, keys) in sections {
+            if !text.is_empty() {
+                text.push(Spans::from(""));
+            }
+            text.push(Spans::from(Span::styled(title, self.theme.title().add_modifier(Modifier::BOLD))));
+            for (keys, description) in keys {
+                text.push(Spans::from(vec![
+                    Span::styled(format!("  {:>width$}  ", keys, width = width), self.theme.text()),
+                    Span::styled(description, self.theme.accent()),
+                ]));
+            }
+        }
+        text
+    }
+
+    fn draw_help<'a>(&'a self) -> Paragraph<'a> {
+        Paragraph::new(self.help_text())
+            .block(
+                Block::default()
+                .title(Span::styled("Keys · Esc to close", self.theme.popup_title("Info")))
+                .borders(Borders::ALL)
+                .style(self.theme.popup())
             )
-        ])];
-        if self.popup.show_popup {
-            text.push(
-                Spans::from(vec![
-                    Span::raw(
-                        "    ",
-                    ),
-                    Span::styled(
-                        "q: ",
-                        Style::default()
-                        .fg(Color::Rgb(213, 196, 161))
-                    ),
-                    Span::styled(
-                        "Close Popup",
-                        Style::default().fg(Color::Green)
-                    ),
-                ])  
+            .scroll((self.help_scroll, 0))
+    }
+
+    fn draw_cite<'a>(&'a self) -> Paragraph<'a> {
+        let citation = match self.results.get(self.result_index) {
+            Some(result) => przzi_cite::format(result, self.citation_style),
+            None => String::new(),
+        };
+        let text = citation.lines().map(|line| Spans::from(Span::styled(line.to_string(), self.theme.text()))).collect::<Vec<Spans>>();
+        Paragraph::new(text)
+            .block(
+                Block::default()
+                .title(Span::styled(
+                    format!("{} · Tab: next style · Enter: copy · Esc: close", self.citation_style.name()),
+                    self.theme.popup_title("Info")
+                ))
+                .borders(Borders::ALL)
+                .style(self.theme.popup())
             )
+            .wrap(Wrap {trim: false})
+    }
+
+    /// Only the keys needed most, the rest are in the help overlay
+    fn footer_lines(&self) -> Vec<FooterLine> {
+        let hint = |action: Action| (vec![action], action.description().to_string());
+        let mut lines = vec![
+            self.footer_line(vec![
+                (vec![Action::NextResult, Action::PreviousResult], "Next/Previous".to_string()),
+                (vec![Action::ScrollDown, Action::ScrollUp], "Scroll Abstract".to_string()),
+                hint(Action::OpenInBrowser),
+                hint(Action::Download),
+                hint(Action::Search),
+                hint(Action::Help),
+            ]),
+        ];
+        if let Some(find) = &self.find {
+            lines.push(self.footer_line(vec![
+                (vec![Action::FindNext, Action::FindPrevious], format!("Next/Previous \"{}\"", find.input)),
+                hint(Action::StopFind),
+            ]));
+        }
+        let mut extra = Vec::new();
+        let tasks = self.workers.tasks();
+        if !tasks.is_empty() {
+            // What is running in the background goes first so it is not cut off
+            let running = tasks
+                .iter()
+                .map(|task| match &task.progress {
+                    Some(progress) => format!("{} {} · {}", task.spinner(), task.label, progress),
+                    None => format!("{} {}", task.spinner(), task.label),
+                })
+                .collect::<Vec<String>>()
+                .join("    ");
+            lines.insert(0, FooterLine {
+                text: Spans::from(Span::styled(running, self.theme.accent())),
+                buttons: Vec::new(),
+            });
+            extra.push(hint(Action::Cancel));
+        }
+        if !self.back_stack.is_empty() {
+            extra.push(hint(Action::Back));
+        }
+        if self.popup.show_popup {
+            extra.push(hint(Action::ClosePopup));
+        }
+        if !extra.is_empty() {
+            lines.push(self.footer_line(extra));
         }
-        Paragraph::new(text.clone())
+        lines
+    }
+
+    /// Rows the footer takes at a given width, long lines wrap
+    fn footer_rows(&self, width: u16) -> u16 {
+        let editing = self.prompt.is_some()
+            || self.editing_filter
+            || self.find.as_ref().is_some_and(|find| find.editing);
+        if editing {
+            // The prompt, filter and find boxes are three lines each
+            return 3;
+        }
+        self.footer_lines()
+            .iter()
+            .map(|line| (line.text.width() as u16).div_ceil(width.max(1)).max(1))
+            .sum()
+    }
+
+    /// Also returns where each hint was drawn in `area`, so it can be clicked
+    fn draw_footer<'a>(&'a self, area: Rect) -> (Paragraph<'a>, Vec<(Rect, Action)>) {
+        let lines = self.footer_lines();
+        let buttons = Self::place_buttons(&lines, area);
+        let text = lines.into_iter().map(|line| line.text).collect::<Vec<Spans>>();
+        let footer = Paragraph::new(text)
             .alignment(Alignment::Center)
-            .wrap(Wrap {trim: true})    
+            .wrap(Wrap {trim: true});
+        (footer, buttons)
     }
 
+    /// Does what a key or a click stands for, returns whether to quit
+    fn perform(&mut self, action: Action) -> bool {
+        match action {
+            Action::Quit => return true,
+            Action::Help => {
+                self.popup.close();
+                self.show_help = true;
+                self.help_scroll = 0;
+            },
+            Action::FindNext => {
+                self.jump_to_found(1);
+            },
+            Action::FindPrevious => {
+                self.jump_to_found(-1);
+            },
+            Action::StopFind => {
+                self.find = None;
+            },
+            Action::Find if !self.results.is_empty() => {
+                self.popup.close();
+                self.find = Some(Find { input: String::new(), editing: true, current: 0 });
+            },
+            Action::NextResult if self.result_index + 1 < self.results.len() => {
+                self.select(self.result_index + 1);
+            },
+            Action::PreviousResult if self.result_index > 0 => {
+                self.select(self.result_index - 1);
+            },
+            Action::FirstResult if !self.results.is_empty() => {
+                self.select(0);
+            },
+            Action::LastResult if !self.results.is_empty() => {
+                self.select(self.results.len() - 1);
+            },
+            Action::OpenInBrowser if !self.results.is_empty() => {
+                if let Err(_e) = webbrowser::open(self.results[self.result_index].url.as_str()) {
+                    self.popup.open("Redirect failed! Please try again".to_string(), "Error!".to_string());
+                }
+            },
+            Action::Download if !self.results.is_empty() => {
+                self.start_download();
+            },
+            Action::CopyDoi if !self.results.is_empty() => {
+                let doi = self.results[self.result_index].doi.clone();
+                self.copy("DOI", doi);
+            },
+            Action::CopyUrl if !self.results.is_empty() => {
+                let url = self.results[self.result_index].url.to_string();
+                self.copy("URL", Some(url));
+            },
+            Action::CopyTitle if !self.results.is_empty() => {
+                let title = self.results[self.result_index].title.clone();
+                self.copy("title", Some(title));
+            },
+            Action::CopyBibtex if !self.results.is_empty() => {
+                let entry = przzi_cite::bibtex(&self.results[self.result_index]);
+                self.copy("BibTeX entry", Some(entry));
+            },
+            Action::CopyCitation if !self.results.is_empty() => {
+                self.copy_citation();
+            },
+            Action::Cite if !self.results.is_empty() => {
+                self.popup.close();
+                self.cite_preview = true;
+            },
+            Action::ClosePopup => {
+                self.popup.close();
+            },
+            // Stops the latest background task, or closes the popup when nothing is running
+            Action::Cancel => match self.workers.cancel_latest() {
+                Some(task) => self.popup.open(format!("Cancelled: {}", task.label), "Info".to_string()),
+                None => self.popup.close(),
+            },
+            Action::References if !self.results.is_empty() => {
+                self.open_linked(Link::References);
+            },
+            Action::Citations if !self.results.is_empty() => {
+                self.open_linked(Link::Citations);
+            },
+            Action::Authors => {
+                self.popup.close();
+                self.pick_author();
+            },
+            Action::Like if !self.results.is_empty() => {
+                self.toggle_feedback(true);
+            },
+            Action::Dislike if !self.results.is_empty() => {
+                self.toggle_feedback(false);
+            },
+            Action::Recommend => {
+                self.open_recommendations();
+            },
+            Action::Sort => {
+                self.popup.close();
+                self.cycle_sort();
+            },
+            Action::Back => {
+                self.popup.close();
+                self.pop_results();
+            },
+            Action::NextMatch if !self.results.is_empty() => {
+                self.popup.close();
+                self.jump_to_match(true);
+            },
+            Action::PreviousMatch if !self.results.is_empty() => {
+                self.popup.close();
+                self.jump_to_match(false);
+            },
+            Action::Filter => {
+                self.popup.close();
+                self.editing_filter = true;
+            },
+            Action::Search => {
+                self.popup.close();
+                self.prompt = Some(SearchPrompt::new());
+            },
+            Action::ScrollUp => {
+                self.scroll_to(self.scroll.saturating_sub(1));
+            },
+            Action::ScrollDown => {
+                self.scroll_to(self.scroll.saturating_add(1));
+            },
+            // A page keeps its last line in view
+            Action::PageUp => {
+                self.scroll_to(self.scroll.saturating_sub(self.abstract_rows().saturating_sub(1).max(1)));
+            },
+            Action::PageDown => {
+                self.scroll_to(self.scroll.saturating_add(self.abstract_rows().saturating_sub(1).max(1)));
+            },
+            Action::ScrollToTop => {
+                self.scroll_to(0);
+            },
+            Action::ScrollToBottom => {
+                self.scroll_to(self.max_scroll());
+            },
+            _ => {}
+        }
+        false
+    }
+
+    /// Takes over the terminal until the user quits, it is given back on errors and panics too
     pub fn start_ui(&mut self) -> Result<(), PRZZIError> {
-        let mut stdout = stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-        let mut backend = CrosstermBackend::new(stdout);
+        let _guard = TerminalGuard::new(self.mouse)?;
+        let mut backend = CrosstermBackend::new(stdout());
         backend.execute(SetTitle("Paperazzi"))?;
         let mut terminal = Terminal::new(backend)?;
         terminal.clear()?;
+        if let Some(start) = self.start_fetch.take() {
+            self.fetch_start(start);
+        }
         loop {
+            self.receive();
             terminal.draw(|f| self.draw(f))?;
-            match self.read_key()? {
-                KeyEvent {
-                    code: KeyCode::Char('c'),
-                    modifiers: KeyModifiers::CONTROL
-                } => {
-                    terminal.clear()?;
-                    execute!(
-                        terminal.backend_mut(),
-                        LeaveAlternateScreen,
-                        DisableMouseCapture
-                    )?;
-                    break;
-                },
-                KeyEvent {
-                    code: KeyCode::Char('n'),
-                    modifiers: KeyModifiers::NONE
-                } => {
-                    if self.result_index < self.results.len() - 1 {
-                        self.popup.close();
-                        self.result_index += 1;
-                    }
-                },
-                KeyEvent {
-                    code: KeyCode::Char('p'),
-                    modifiers: KeyModifiers::NONE
-                } => {
-                   if self.result_index > 0 {
-                       self.popup.close();
-                       self.result_index -= 1;
-                   }
-                },
-               KeyEvent {
-                    code: KeyCode::Char('r'),
-                    modifiers: KeyModifiers::CONTROL
-                } => {
-                    if webbrowser::open(&self.results[self.result_index].url.as_str()).is_err() {
-                        self.popup.open("Redirect failed! Please try again".to_string(), "Error!".to_string());
-                    }
-                },
-                KeyEvent {
-                    code: KeyCode::Char('d'),
-                    modifiers: KeyModifiers::CONTROL
-                } => {
-                    self.popup.open("Attempting to Download".to_string(), "Info".to_string());
-                    terminal.draw(|f| self.draw(f))?;
-                    if self.results[self.result_index].url.to_string().contains("doi") {
-                        let mut download_url = "https://sci-hub.wf/".to_string();
-                        download_url.push_str(self.results[self.result_index].url.as_ref());
-                        let doi_url =  Url::parse(&download_url)?;
-                        if let Err(_e) = PRZZI::download_doi(doi_url) {
-                            self.popup.open("Download failed! This paper is not available to download yet :( if you think this is wrong, raise a issue :) \n Please try redirecting instead".to_string(), "Error!".to_string());
-                        }
-                        else{
-                            self.popup.open("Download Complete :) !!".to_string(), "Success".to_string());
-
+            let key = match self.read_event()? {
+                Some(Event::Key(key)) => key,
+                Some(Event::Mouse(mouse)) => {
+                    if let Some(action) = self.handle_mouse(mouse) {
+                        if self.perform(action) {
+                            break;
                         }
                     }
-                    else {
-                        self.popup.open("This paper doesnt have a valid DOI so a download isnt possible just yet :( If you think this is wrong, feel free to raise an issue \n Please try redirect instead".to_string(), "Error!".to_string());
-                    }
-                },
-                KeyEvent {
-                    code: KeyCode::Char('q'),
-                    modifiers: KeyModifiers::NONE
-                } => {
-                    self.popup.close();
-                },
-                KeyEvent {
-                    code: KeyCode::Up,
-                    modifiers: KeyModifiers::NONE
-                } => {
-                    if self.scroll > 0 {
-                        self.scroll-=1;
-                    }
-                },
-                KeyEvent {
-                    code: KeyCode::Down,
-                    modifiers: KeyModifiers::NONE
-                } => {
-                    self.scroll+=1;
+                    continue;
                 },
-                _ => {}
+                _ => continue,
+            };
+            if self.show_help {
+                self.handle_help_key(key);
+                continue;
+            }
+            if self.cite_preview {
+                self.handle_cite_key(key);
+                continue;
+            }
+            if self.picker.is_some() {
+                self.handle_picker_key(key);
+                continue;
+            }
+            if self.prompt.is_some() {
+                self.handle_prompt_key(key);
+                continue;
+            }
+            if self.editing_filter {
+                self.handle_filter_key(key);
+                continue;
+            }
+            if self.find.as_ref().is_some_and(|find| find.editing) {
+                self.handle_find_key(key);
+                continue;
+            }
+            let action = match self.next_action(key) {
+                Some(action) => action,
+                None => continue,
+            };
+            if self.perform(action) {
+                break;
             }
-            
         }
+        terminal.clear()?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_author_picker_without_authors() {
+        let mut tui = PRZZITUI::new();
+        tui.pick_author();
+        assert!(tui.picker.is_none());
+        assert_eq!(tui.popup.popup_type, "Info");
+
+        // An empty picker from elsewhere does nothing on Enter
+        tui.picker = Some(AuthorPicker::new("Authors".to_string(), Vec::new()));
+        tui.choose_author();
+        assert!(tui.picker.is_none());
+    }
+}
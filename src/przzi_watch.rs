@@ -0,0 +1,263 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use clap::{ArgEnum, Args, Subcommand};
+use serde::{Deserialize, Serialize};
+
+use crate::przzi_api::PRZZIClient;
+use crate::przzi_history;
+use crate::{PRZZIError, PRZZIResult, Query, SearchFilters};
+
+const WATCHES_FILE: &str = "watches.json";
+
+/// Save searches and get told about papers that are new since the last run
+#[derive(Args)]
+pub struct WatchConfig {
+    #[clap(subcommand)]
+    pub command: WatchCommand,
+}
+
+#[derive(Subcommand)]
+pub enum WatchCommand {
+    /// Save a search as a watch, the papers it finds now are marked as seen
+    Add(WatchAddConfig),
+    /// List the saved watches
+    List,
+    /// Forget a watch
+    Remove {
+        /// name of the watch
+        name: String,
+    },
+    /// Run every watch and report the papers not seen before
+    Run(WatchRunConfig),
+}
+
+#[derive(Args)]
+pub struct WatchAddConfig {
+    /// name to refer to the watch by
+    pub name: String,
+
+    /// query to search for
+    pub query: String,
+
+    #[clap(flatten)]
+    pub filters: SearchFilters,
+
+    /// number of results to check on every run
+    #[clap(short = 'r', long = "num_results", default_value_t = 50)]
+    pub num_results: usize,
+}
+
+#[derive(Args)]
+pub struct WatchRunConfig {
+    /// only run the watch with this name
+    #[clap(long = "name")]
+    pub name: Option<String>,
+
+    /// how to print the new papers
+    #[clap(short = 'f', long = "format", arg_enum, default_value = "text")]
+    pub format: WatchFormat,
+
+    /// browse the new papers in the TUI instead of printing them
+    #[clap(long = "tui")]
+    pub tui: bool,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum WatchFormat {
+    Text,
+    Json,
+}
+
+/// A saved search and the papers it has already reported
+#[derive(Serialize, Deserialize)]
+pub struct Watch {
+    pub name: String,
+    pub query: String,
+    #[serde(default)]
+    pub filters: SearchFilters,
+    pub num_results: usize,
+    pub created: DateTime<Utc>,
+    pub last_run: Option<DateTime<Utc>>,
+    /// IDs of every paper reported so far
+    pub seen: BTreeSet<String>,
+}
+
+/// What one watch found on `watch run`
+#[derive(Serialize)]
+pub struct WatchReport {
+    pub watch: String,
+    pub query: String,
+    pub new: Vec<PRZZIResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn watches_path() -> Result<PathBuf, PRZZIError> {
+    Ok(crate::data_dir()?.join(WATCHES_FILE))
+}
+
+pub fn load() -> Result<Vec<Watch>, PRZZIError> {
+    let path = watches_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn save(watches: &[Watch]) -> Result<(), PRZZIError> {
+    fs::write(watches_path()?, serde_json::to_string_pretty(watches)?)?;
+    Ok(())
+}
+
+/// The key a paper is remembered by, its Semantic Scholar ID when it has one
+fn paper_key(result: &PRZZIResult) -> String {
+    result.paper_id.clone().unwrap_or_else(|| result.url.to_string())
+}
+
+fn search(client: &PRZZIClient, watch: &Watch) -> Result<Vec<PRZZIResult>, PRZZIError> {
+    client.search(&Query::new(&watch.query, watch.num_results, &watch.filters))
+}
+
+/// The reports of a `watch run`, and the watches with the papers it found marked as seen
+pub struct WatchRun {
+    pub reports: Vec<WatchReport>,
+    watches: Vec<Watch>,
+}
+
+impl WatchRun {
+    /// Remembers the papers the run found. Call it once they were reported, a run given up on
+    /// before that reports them again next time
+    pub fn save(&self) -> Result<(), PRZZIError> {
+        save(&self.watches)
+    }
+}
+
+/// Runs the saved watches, or only the one called `name`, without saving what they found.
+///
+/// A watch that fails is reported and the rest still run
+pub fn run_watches(client: &PRZZIClient, name: Option<&str>) -> Result<WatchRun, PRZZIError> {
+    let mut watches = load()?;
+    if let Some(name) = name {
+        if !watches.iter().any(|watch| watch.name == name) {
+            return Err(PRZZIError {
+                msg: format!("There is no watch named \"{}\"", name),
+            });
+        }
+    }
+    let mut reports: Vec<WatchReport> = Vec::new();
+    for watch in watches.iter_mut().filter(|watch| name.is_none_or(|name| watch.name == name)) {
+        let mut report = WatchReport {
+            watch: watch.name.clone(),
+            query: watch.query.clone(),
+            new: Vec::new(),
+            error: None,
+        };
+        match search(client, watch) {
+            Ok(results) => {
+                for result in results {
+                    if watch.seen.insert(paper_key(&result)) {
+                        report.new.push(result);
+                    }
+                }
+                watch.last_run = Some(Utc::now());
+            },
+            Err(e) => report.error = Some(e.msg),
+        }
+        reports.push(report);
+    }
+    Ok(WatchRun { reports, watches })
+}
+
+/// Runs a `watch` subcommand, except `watch run --tui` which the TUI runs itself
+pub fn run(client: &PRZZIClient, config: &WatchConfig) -> Result<(), PRZZIError> {
+    let mut watches = load()?;
+    match &config.command {
+        WatchCommand::Add(add) => {
+            if watches.iter().any(|watch| watch.name == add.name) {
+                return Err(PRZZIError {
+                    msg: format!("A watch named \"{}\" already exists", add.name),
+                });
+            }
+            let mut watch = Watch {
+                name: add.name.clone(),
+                query: add.query.clone(),
+                filters: add.filters.clone(),
+                num_results: add.num_results,
+                created: Utc::now(),
+                last_run: None,
+                seen: BTreeSet::new(),
+            };
+            let results = search(client, &watch)?;
+            watch.seen = results.iter().map(paper_key).collect();
+            watch.last_run = Some(Utc::now());
+            println!("Watching \"{}\", {} current papers marked as seen", watch.name, watch.seen.len());
+            watches.push(watch);
+            save(&watches)?;
+        },
+        WatchCommand::List => {
+            if watches.is_empty() {
+                println!("No watches saved yet, add one with `paperazzi watch add <name> <query>`");
+            }
+            for watch in &watches {
+                let mut line = format!("{}  \"{}\"", watch.name, watch.query);
+                let filters = watch.filters.describe();
+                if !filters.is_empty() {
+                    line.push_str(&format!("  ({})", filters));
+                }
+                let last_run = watch.last_run.as_ref().map(przzi_history::local_time).unwrap_or_else(|| "never".to_string());
+                line.push_str(&format!("  · {} seen · last run {}", watch.seen.len(), last_run));
+                println!("{}", line);
+            }
+        },
+        WatchCommand::Remove { name } => {
+            let before = watches.len();
+            watches.retain(|watch| watch.name != *name);
+            if watches.len() == before {
+                return Err(PRZZIError {
+                    msg: format!("There is no watch named \"{}\"", name),
+                });
+            }
+            save(&watches)?;
+            println!("Removed \"{}\"", name);
+        },
+        WatchCommand::Run(run) => {
+            let ran = run_watches(client, run.name.as_deref())?;
+            match run.format {
+                WatchFormat::Json => println!("{}", serde_json::to_string_pretty(&ran.reports)?),
+                WatchFormat::Text => {
+                    let text = format_reports(&ran.reports);
+                    if !text.is_empty() {
+                        println!("{}", text);
+                    }
+                },
+            }
+            ran.save()?;
+        },
+    }
+    Ok(())
+}
+
+/// Plain text report of the new papers, empty when there is nothing new so cron stays quiet
+pub fn format_reports(reports: &[WatchReport]) -> String {
+    let mut blocks: Vec<String> = Vec::new();
+    for report in reports {
+        if let Some(error) = &report.error {
+            blocks.push(format!("{} (\"{}\"): failed, {}", report.watch, report.query, error));
+            continue;
+        }
+        if report.new.is_empty() {
+            continue;
+        }
+        let mut lines = vec![format!("{} (\"{}\"): {} new", report.watch, report.query, report.new.len())];
+        for result in &report.new {
+            let year = result.year.map(|year| year.to_string()).unwrap_or_else(|| "----".to_string());
+            lines.push(format!("  {}  {}", year, result.title));
+            lines.push(format!("        {}", result.url));
+        }
+        blocks.push(lines.join("\n"));
+    }
+    blocks.join("\n\n")
+}
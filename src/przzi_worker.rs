@@ -0,0 +1,260 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+/// Frames of the spinner shown next to running tasks, one every `SPINNER_FRAME_MS`
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_FRAME_MS: u128 = 100;
+
+/// Work running on a background thread, as the UI shows it
+pub struct Task {
+    pub id: usize,
+    /// What is being done, e.g. `Searching "attention"`
+    pub label: String,
+    /// How far along it is, e.g. the bytes downloaded so far
+    pub progress: Option<String>,
+    started: Instant,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Task {
+    pub fn spinner(&self) -> &'static str {
+        SPINNER[(self.started.elapsed().as_millis() / SPINNER_FRAME_MS) as usize % SPINNER.len()]
+    }
+}
+
+/// A task whose work panicked, reported to the UI rather than printed over it
+pub struct Failed {
+    /// The label of the task
+    pub label: String,
+    /// The panic message
+    pub reason: String,
+}
+
+/// What a task's thread sends over the channel
+enum Message<T> {
+    Sent(T),
+    Done,
+    Panicked(String),
+}
+
+/// Given to the work on its thread to send messages back to the UI.
+/// Dropping it when the work returns tells the UI the task is over
+pub struct Reporter<T> {
+    id: usize,
+    sender: Sender<(usize, Message<T>)>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<T> Reporter<T> {
+    /// Whether the task was cancelled, work that can stop early should check this
+    pub fn cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Sends a message to the UI, returns `false` once the task was cancelled or the UI is gone
+    pub fn send(&self, message: T) -> bool {
+        !self.cancelled() && self.sender.send((self.id, Message::Sent(message))).is_ok()
+    }
+}
+
+impl<T> Drop for Reporter<T> {
+    fn drop(&mut self) {
+        // A panicking task is reported as failed once the panic is caught
+        if !thread::panicking() {
+            let _ = self.sender.send((self.id, Message::Done));
+        }
+    }
+}
+
+/// The message a panic was started with
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "unknown error".to_string()),
+    }
+}
+
+/// Runs requests and downloads on background threads so the UI keeps drawing while they wait.
+/// Each task sends messages of type `T` back over a channel.
+///
+/// Blocking requests cant be interrupted, so cancelling a task only stops its messages from
+/// being delivered, unless the work itself checks `Reporter::cancelled`.
+/// A panic in the work is caught and reported as the task having failed
+pub struct Workers<T> {
+    sender: Sender<(usize, Message<T>)>,
+    receiver: Receiver<(usize, Message<T>)>,
+    /// Running tasks, oldest first
+    tasks: Vec<Task>,
+    next_id: usize,
+}
+
+impl<T: Send + 'static> Default for Workers<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send + 'static> Workers<T> {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Workers {
+            sender,
+            receiver,
+            tasks: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Starts `work` on its own thread, returns the task's ID
+    pub fn spawn<F>(&mut self, label: String, work: F) -> usize
+    where
+        F: FnOnce(Reporter<T>) + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let reporter = Reporter {
+            id,
+            sender: self.sender.clone(),
+            cancelled: Arc::clone(&cancelled),
+        };
+        self.tasks.push(Task {
+            id,
+            label,
+            progress: None,
+            started: Instant::now(),
+            cancelled,
+        });
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| work(reporter))) {
+                let _ = sender.send((id, Message::Panicked(panic_message(&*payload))));
+            }
+        });
+        id
+    }
+
+    /// Messages sent since the last call by tasks that were not cancelled, and the tasks that panicked.
+    /// Tasks that ended are forgotten
+    pub fn poll(&mut self) -> Vec<(usize, Result<T, Failed>)> {
+        let mut messages = Vec::new();
+        while let Ok((id, message)) = self.receiver.try_recv() {
+            let index = match self.tasks.iter().position(|task| task.id == id) {
+                Some(index) => index,
+                None => continue,
+            };
+            match message {
+                Message::Sent(message) => messages.push((id, Ok(message))),
+                Message::Done => {
+                    self.tasks.remove(index);
+                },
+                Message::Panicked(reason) => {
+                    let task = self.tasks.remove(index);
+                    messages.push((id, Err(Failed { label: task.label, reason })));
+                },
+            }
+        }
+        messages
+    }
+
+    pub fn set_progress(&mut self, id: usize, progress: String) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.id == id) {
+            task.progress = Some(progress);
+        }
+    }
+
+    /// Cancels a task, nothing it sends is delivered afterwards
+    pub fn cancel(&mut self, id: usize) -> Option<Task> {
+        let index = self.tasks.iter().position(|task| task.id == id)?;
+        let task = self.tasks.remove(index);
+        task.cancelled.store(true, Ordering::SeqCst);
+        Some(task)
+    }
+
+    /// Cancels the task started last
+    pub fn cancel_latest(&mut self) -> Option<Task> {
+        let id = self.tasks.last()?.id;
+        self.cancel(id)
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Polls until every task ended, the messages in the order they came
+    fn wait(workers: &mut Workers<u32>) -> Vec<(usize, Result<u32, Failed>)> {
+        let mut messages = Vec::new();
+        let started = Instant::now();
+        while !workers.tasks().is_empty() {
+            assert!(started.elapsed() < Duration::from_secs(5), "tasks did not end");
+            messages.extend(workers.poll());
+            thread::sleep(Duration::from_millis(5));
+        }
+        messages.extend(workers.poll());
+        messages
+    }
+
+    #[test]
+    fn delivers_messages() {
+        let mut workers = Workers::new();
+        let id = workers.spawn("Counting".to_string(), |reporter| {
+            reporter.send(1);
+            reporter.send(2);
+        });
+        let messages: Vec<(usize, u32)> = wait(&mut workers)
+            .into_iter()
+            .map(|(id, message)| (id, message.ok().unwrap()))
+            .collect();
+        assert_eq!(messages, [(id, 1), (id, 2)]);
+    }
+
+    #[test]
+    fn reports_panics() {
+        let mut workers = Workers::new();
+        let id = workers.spawn("Exploding".to_string(), |reporter| {
+            reporter.send(1);
+            panic!("boom");
+        });
+        let messages = wait(&mut workers);
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[0], (sent, Ok(1)) if sent == id));
+        match &messages[1] {
+            (failed, Err(Failed { label, reason })) => {
+                assert_eq!(*failed, id);
+                assert_eq!(label, "Exploding");
+                assert_eq!(reason, "boom");
+            }
+            _ => panic!("the panic was not reported"),
+        }
+    }
+
+    #[test]
+    fn drops_cancelled() {
+        let mut workers = Workers::new();
+        let (go, wait_for_go) = mpsc::channel::<()>();
+        let (seen, saw) = mpsc::channel();
+        let id = workers.spawn("Waiting".to_string(), move |reporter| {
+            let _ = wait_for_go.recv();
+            let _ = seen.send((reporter.cancelled(), reporter.send(1)));
+        });
+        assert_eq!(workers.cancel(id).map(|task| task.id), Some(id));
+        assert!(workers.tasks().is_empty());
+        go.send(()).unwrap();
+        assert_eq!(saw.recv_timeout(Duration::from_secs(5)), Ok((true, false)));
+        assert!(workers.poll().is_empty());
+    }
+}